@@ -0,0 +1,120 @@
+use calmram_lib::{
+    protocol::{BoardOrder, BoardServerMessage},
+    OrderStatus,
+};
+use iced::{
+    alignment::Horizontal,
+    widget::{column, container, horizontal_rule, row, stack, text, vertical_rule, Image},
+    Color, ContentFit, Element,
+    Length::Fill,
+    Task,
+};
+
+use crate::{fullscreen, network::Server, settings, theme, CHILL_FONT};
+
+/// The pickup board: order numbers being prepared and ready to collect.
+struct State {
+    orders: Vec<BoardOrder>,
+    /// Held so the connection stays open; the board never sends anything.
+    _server: Server<()>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    Server(BoardServerMessage),
+}
+
+fn update(state: &mut State, message: Message) -> Task<Message> {
+    match message {
+        Message::Server(BoardServerMessage::Orders(orders)) => {
+            state.orders = orders;
+        }
+        Message::Server(BoardServerMessage::OrderStatusChanged(order)) => {
+            state
+                .orders
+                .retain(|o| o.order_number != order.order_number);
+            if order.status.is_open() {
+                state.orders.push(order);
+            }
+        }
+    }
+    Task::none()
+}
+
+fn orders_view<'a>(
+    title: &'a str,
+    orders: impl Iterator<Item = &'a BoardOrder>,
+) -> Element<'a, Message> {
+    let numbers = row(orders.map(|order| {
+        text(order.order_number.to_string())
+            .size(80)
+            .color(Color::BLACK)
+            .into()
+    }))
+    .spacing(40)
+    .wrap();
+
+    column![
+        text(title)
+            .font(CHILL_FONT)
+            .size(100)
+            .color(Color::BLACK)
+            .width(Fill)
+            .align_x(Horizontal::Center),
+        horizontal_rule(2),
+        numbers,
+    ]
+    .spacing(20)
+    .padding(40)
+    .width(Fill)
+    .into()
+}
+
+fn view(state: &State) -> Element<'_, Message> {
+    // A received order is as good as being prepared to whoever is waiting.
+    let preparing = state
+        .orders
+        .iter()
+        .filter(|order| order.status != OrderStatus::Ready);
+    let ready = state
+        .orders
+        .iter()
+        .filter(|order| order.status == OrderStatus::Ready);
+
+    stack!(
+        Image::new("assets/background.png").content_fit(ContentFit::Fill),
+        container(row![
+            orders_view("Preparing", preparing),
+            vertical_rule(2),
+            orders_view("Ready", ready),
+        ])
+        .height(Fill)
+        .style(|_| container::Style {
+            background: Some(iced::Background::Color(Color::from_rgba(
+                1.0, 1.0, 1.0, 0.6,
+            ))),
+            ..Default::default()
+        }),
+    )
+    .into()
+}
+
+pub fn run() -> iced::Result {
+    let (server, server_messages) = Server::connect::<BoardServerMessage>("board");
+
+    iced::application("CalmRam Pickup", update, view)
+        .settings(settings())
+        .theme(theme)
+        .run_with(move || {
+            (
+                State {
+                    orders: Vec::new(),
+                    _server: server,
+                },
+                Task::batch([
+                    fullscreen().discard(),
+                    Task::run(server_messages, Message::Server),
+                ]),
+            )
+        })
+}
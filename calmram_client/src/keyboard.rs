@@ -0,0 +1,64 @@
+use iced::{
+    widget::{button, column, row, text},
+    Color, Element,
+    Length::Fill,
+};
+use iced_fonts::{nerd::icon_to_string, Nerd, NERD_FONT};
+
+use crate::CHILL_FONT;
+
+const ROWS: [&str; 4] = ["1234567890", "QWERTYUIOP", "ASDFGHJKL", "ZXCVBNM"];
+const KEYPAD_ROWS: [&str; 4] = ["123", "456", "789", "0"];
+
+fn key<'a, M: Clone + 'a>(label: String, font: iced::Font, message: M) -> Element<'a, M> {
+    button(text(label).font(font).size(50).center())
+        .on_press(message)
+        .width(Fill)
+        .height(Fill)
+        .style(|_, _| button::Style {
+            background: Some(iced::Background::Color(Color::from_rgba(
+                1.0, 1.0, 1.0, 0.8,
+            ))),
+            text_color: Color::BLACK,
+            ..Default::default()
+        })
+        .into()
+}
+
+/// An on-screen keyboard of digits and capital letters, for kiosks without
+/// a physical one.
+pub fn keyboard<'a, M: Clone + 'a>(on_key: impl Fn(char) -> M, on_backspace: M) -> Element<'a, M> {
+    keys(&ROWS, on_key, on_backspace)
+}
+
+/// An on-screen keypad of just digits, for entering amounts.
+pub fn keypad<'a, M: Clone + 'a>(on_key: impl Fn(char) -> M, on_backspace: M) -> Element<'a, M> {
+    keys(&KEYPAD_ROWS, on_key, on_backspace)
+}
+
+fn keys<'a, M: Clone + 'a>(
+    layout: &[&str],
+    on_key: impl Fn(char) -> M,
+    on_backspace: M,
+) -> Element<'a, M> {
+    let rows = layout.iter().enumerate().map(|(index, keys)| {
+        let keys = keys
+            .chars()
+            .map(|c| key(c.to_string(), CHILL_FONT, on_key(c)))
+            .collect::<Vec<_>>();
+        // Backspace goes at the end of the last row.
+        let backspace = (index == layout.len() - 1).then(|| {
+            key(
+                icon_to_string(Nerd::Backspace),
+                NERD_FONT,
+                on_backspace.clone(),
+            )
+        });
+        row(keys.into_iter().chain(backspace))
+            .spacing(10)
+            .height(Fill)
+            .into()
+    });
+
+    column(rows).spacing(10).into()
+}
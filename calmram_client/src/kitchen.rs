@@ -0,0 +1,175 @@
+use calmram_lib::{
+    protocol::{KitchenClientMessage, KitchenServerMessage, Ticket, TicketItem},
+    OrderStatus,
+};
+use iced::{
+    alignment::Horizontal,
+    widget::{button, column, container, horizontal_rule, row, scrollable, stack, text, Image},
+    Color, ContentFit, Element,
+    Length::{Fill, Fixed},
+    Task,
+};
+
+use crate::{fullscreen, network::Server, settings, theme, CHILL_FONT};
+
+/// The kitchen display: every open ticket, oldest first, until it is picked up
+/// or cancelled by a manager voiding it.
+struct State {
+    tickets: Vec<Ticket>,
+    server: Server<KitchenClientMessage>,
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    SetStatus(i64, OrderStatus),
+    Server(KitchenServerMessage),
+}
+
+fn update(state: &mut State, message: Message) -> Task<Message> {
+    match message {
+        Message::SetStatus(order_id, status) => {
+            state
+                .server
+                .send(&KitchenClientMessage::SetStatus { order_id, status });
+        }
+        Message::Server(KitchenServerMessage::Tickets(tickets)) => {
+            state.tickets = tickets;
+        }
+        Message::Server(KitchenServerMessage::TicketAdded(ticket)) => {
+            if !state.tickets.iter().any(|t| t.order_id == ticket.order_id) {
+                state.tickets.push(ticket);
+            }
+        }
+        Message::Server(KitchenServerMessage::StatusChanged { order_id, status }) => {
+            if status.is_open() {
+                for ticket in &mut state.tickets {
+                    if ticket.order_id == order_id {
+                        ticket.status = status;
+                    }
+                }
+            } else {
+                state.tickets.retain(|ticket| ticket.order_id != order_id);
+            }
+        }
+    }
+    Task::none()
+}
+
+fn line(content: String) -> iced::widget::Text<'static> {
+    text(content).size(28).color(Color::BLACK)
+}
+
+fn item_view(item: &TicketItem) -> Element<'_, Message> {
+    // One line per modifier group, listing everything chosen from it.
+    let mut groups: Vec<(&str, Vec<String>)> = Vec::new();
+    for modifier in &item.modifiers {
+        let name = match modifier.quantity {
+            1 => modifier.name.clone(),
+            quantity => format!("{quantity} x {}", modifier.name),
+        };
+        match groups.last_mut() {
+            Some((group, names)) if *group == modifier.group => names.push(name),
+            _ => groups.push((&modifier.group, vec![name])),
+        }
+    }
+
+    column![line(format!("{} x {}", item.quantity, item.name))]
+        .extend(
+            groups
+                .into_iter()
+                .map(|(group, names)| line(format!("{group}: {}", names.join(", "))).into()),
+        )
+        .into()
+}
+
+fn ticket_view(ticket: &Ticket) -> Element<'_, Message> {
+    let items = ticket.items.iter().map(|item| {
+        column![horizontal_rule(2), item_view(item)]
+            .spacing(10)
+            .into()
+    });
+
+    let status_button = |label: &str, status: OrderStatus| {
+        button(
+            text(label.to_string())
+                .font(CHILL_FONT)
+                .size(40)
+                .width(Fill)
+                .align_x(Horizontal::Center),
+        )
+        .on_press(Message::SetStatus(ticket.order_id, status))
+        .width(Fill)
+        .style(|_, _| button::Style {
+            background: Some(iced::Background::Color(Color::from_rgb8(219, 84, 97))),
+            text_color: Color::BLACK,
+            ..Default::default()
+        })
+    };
+
+    let advance = ticket.status.next().map(|next| {
+        let label = match next {
+            OrderStatus::Preparing => "Start",
+            OrderStatus::Ready => "Ready",
+            _ => "Picked up",
+        };
+        status_button(label, next)
+    });
+
+    container(
+        column![
+            text(format!("#{}", ticket.order_number))
+                .font(CHILL_FONT)
+                .size(60)
+                .color(Color::BLACK),
+            line(ticket.status.to_string()),
+            scrollable(column(items).spacing(10)).height(Fill),
+            column(advance.into_iter().map(Element::from)).spacing(10),
+        ]
+        .spacing(10),
+    )
+    .padding(20)
+    .width(Fixed(320.0))
+    .height(Fill)
+    .style(|_| container::Style {
+        background: Some(iced::Background::Color(Color::from_rgba(
+            1.0, 1.0, 1.0, 0.8,
+        ))),
+        ..Default::default()
+    })
+    .into()
+}
+
+fn view(state: &State) -> Element<'_, Message> {
+    let tickets = row(state.tickets.iter().map(ticket_view))
+        .spacing(20)
+        .padding(20)
+        .height(Fill);
+
+    stack!(
+        Image::new("assets/background.png").content_fit(ContentFit::Fill),
+        scrollable(tickets).direction(scrollable::Direction::Horizontal(
+            scrollable::Scrollbar::default()
+        )),
+    )
+    .into()
+}
+
+pub fn run() -> iced::Result {
+    let (server, server_messages) = Server::connect::<KitchenServerMessage>("kitchen");
+
+    iced::application("CalmRam Kitchen", update, view)
+        .settings(settings())
+        .theme(theme)
+        .run_with(move || {
+            (
+                State {
+                    tickets: Vec::new(),
+                    server,
+                },
+                Task::batch([
+                    fullscreen().discard(),
+                    Task::run(server_messages, Message::Server),
+                ]),
+            )
+        })
+}
@@ -8,17 +8,26 @@ use std::{
     vec,
 };
 
-use calmram_lib::{Base, BaseId, Config, Menu, Order, SpiceLevel, Toppings, ToppingsId};
+use calmram_lib::{
+    protocol::{BoardOrder, ClientMessage, ServerMessage},
+    quote, Cart, CartItem, Category, Config, GroupStyle, ItemId, ItemQuote, Menu, MenuItem,
+    ModifierGroup, ModifierOption, Money, OptionId, Order, OrderStatus, Promotion, Reward, Tender,
+    Tip,
+};
 use iced::{
     alignment::{Horizontal, Vertical},
     daemon::Appearance,
     font::{self, load, Family},
-    futures::{executor::ThreadPool, future::join_all},
+    futures::{
+        executor::{block_on, ThreadPool},
+        future::join_all,
+        StreamExt,
+    },
     padding::{bottom, left, top},
     theme::Palette,
     widget::{
-        button, column, container, horizontal_rule, row, stack, text, vertical_rule, Image, Row,
-        Space,
+        button, column, container, horizontal_rule, row, scrollable, stack, text, vertical_rule,
+        Image, Row, Space,
     },
     window::{change_mode, Mode},
     Color, ContentFit, Element, Font,
@@ -26,9 +35,13 @@ use iced::{
     Padding, Pixels, Radians, Rotation, Settings, Task, Theme,
 };
 use iced_fonts::{nerd::icon_to_string, Nerd, NERD_FONT, NERD_FONT_BYTES};
+use network::Server;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use websocket::{codec::ws, stream::sync::NetworkStream};
+
+mod board;
+mod keyboard;
+mod kitchen;
+mod network;
 
 const SERVER_URL: &str = "localhost:8000";
 const CHILL_FONT: Font = Font {
@@ -41,85 +54,697 @@ const CHILL_FONT: Font = Font {
 #[derive(Debug, Clone)]
 enum Page {
     Menu,
+    Browse,
+    Cart,
     Order,
     OrderComplete,
 }
 
 struct State {
     config: Config,
+    cart: Cart,
+    /// The item being built on the menu page.
     current_order: Order,
+    /// Which item in the cart `current_order` replaces, if it is an edit.
+    editing: Option<usize>,
+    /// The category shown on the browse page.
+    category: usize,
     current_page: Page,
-    ws_server: websocket::sync::Client<Box<dyn NetworkStream + Send>>,
+    order_number: Option<u32>,
+    /// Orders placed at this kiosk that are still being made or waiting to
+    /// be picked up, so customers can keep an eye on them once the kiosk has
+    /// moved on to the next customer.
+    tracked: Vec<BoardOrder>,
+    rejection: Option<String>,
+    /// Whether the order has been sent and the customer is paying for it at
+    /// the terminal.
+    paying: bool,
+    /// The promo code applied to the order, once the server has accepted it.
+    promotion: Option<Promotion>,
+    /// The promo code being typed, while the keyboard is open.
+    promo_entry: Option<String>,
+    promo_error: Option<String>,
+    /// Whether the customer is being asked for their phone number to collect
+    /// points before paying.
+    collecting_points: bool,
+    /// The digits of the phone number being typed on the keypad.
+    phone_entry: String,
+    phone_error: Option<String>,
+    /// The customer's phone number and points, once the server has looked
+    /// them up.
+    member: Option<(String, i64)>,
+    /// The reward the customer is spending points on, in place of a promo
+    /// code.
+    reward: Option<Reward>,
+    /// Whether the customer is being asked for a tip before paying.
+    choosing_tip: bool,
+    /// The cents of a tip being typed, while the keypad is open.
+    tip_entry: Option<String>,
+    tip: Option<Tip>,
+    /// Whether the customer is splitting the order between tenders.
+    splitting: bool,
+    tenders: Vec<Tender>,
+    /// The cents of the next tender's amount, as typed on the keypad.
+    tender_entry: String,
+    /// The gift card code being typed, while the keyboard is open.
+    gift_card_entry: Option<String>,
+    gift_card_error: Option<String>,
+    /// What the last order left to pay in cash at the counter.
+    cash_due: Money,
+    server: Server<ClientMessage>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
-    SelectBase(BaseId),
-    ToggleTopping(ToppingsId),
-    SelectSpiceLevel(i32),
+    ToggleOption(OptionId),
+    AddOption(OptionId),
+    RemoveOption(OptionId),
     SetPage(Page),
+    AddToCart,
+    SelectCategory(usize),
+    ChooseItem(ItemId),
+    EditItem(usize),
+    DuplicateItem(usize),
+    RemoveItem(usize),
+    SetQuantity(usize, u32),
+    EnterPromo,
+    PromoKey(char),
+    PromoBackspace,
+    ApplyPromo,
+    CancelPromo,
+    RemovePromo,
+    Pay,
+    Split,
+    PhoneKey(char),
+    PhoneBackspace,
+    CheckLoyalty,
+    ChooseReward(Reward),
+    RemoveReward,
+    ForgetPhone,
+    SkipLoyalty,
+    ContinueCheckout,
+    CancelLoyalty,
+    ChooseTip(Option<Tip>),
+    EnterCustomTip,
+    TipKey(char),
+    TipBackspace,
+    ApplyCustomTip,
+    CancelCustomTip,
+    CancelTip,
+    TenderKey(char),
+    TenderBackspace,
+    AddTender(Tender),
+    RemoveTender(usize),
+    CancelSplit,
+    EnterGiftCard,
+    GiftCardKey(char),
+    GiftCardBackspace,
+    CheckGiftCard,
+    CancelGiftCard,
+    SubmitOrder,
+    Server(ServerMessage),
     Reset,
 }
 
 fn update(state: &mut State, message: Message) -> Task<Message> {
     match message {
-        Message::SelectBase(base_id) => {
-            state.current_order.base = base_id;
+        Message::ToggleOption(option_id) => {
+            state.current_order.toggle(&state.config.menu, option_id);
+            Task::none()
+        }
+        Message::AddOption(option_id) => {
+            state.current_order.add(&state.config.menu, option_id);
+            Task::none()
+        }
+        Message::RemoveOption(option_id) => {
+            state.current_order.remove(&state.config.menu, option_id);
             Task::none()
         }
-        Message::ToggleTopping(topping_id) => {
-            if state.current_order.toppings.contains(&topping_id) {
-                state.current_order.toppings.retain(|id| id != &topping_id);
+        Message::SetPage(page) => {
+            if matches!(page, Page::Order) {
+                state.rejection = None;
+            }
+            state.current_page = page;
+            Task::none()
+        }
+        Message::AddToCart => {
+            if state.config.menu.item(state.current_order.item).is_none() {
+                return Task::none();
+            }
+            let order = std::mem::replace(&mut state.current_order, new_order(&state.config));
+            match state.editing.take() {
+                Some(index) if index < state.cart.items.len() => {
+                    state.cart.items[index].order = order
+                }
+                _ => {
+                    state.cart.add(order);
+                    state.current_page = Page::Browse;
+                    return Task::none();
+                }
+            }
+            state.current_page = Page::Cart;
+            Task::none()
+        }
+        Message::SelectCategory(category) => {
+            state.category = category;
+            Task::none()
+        }
+        Message::ChooseItem(item_id) => {
+            let order = state.config.menu.new_order(item_id);
+            let has_options = state
+                .config
+                .menu
+                .item(item_id)
+                .is_some_and(|item| !item.modifier_groups.is_empty());
+            // Items with nothing to choose go straight into the cart.
+            if has_options {
+                state.current_order = order;
+                state.editing = None;
+                state.current_page = Page::Menu;
             } else {
-                state.current_order.toppings.push(topping_id);
+                state.cart.add(order);
             }
             Task::none()
         }
-        Message::SelectSpiceLevel(spice_level_id) => {
-            state.current_order.spice_level = spice_level_id;
+        Message::EditItem(index) => {
+            if let Some(item) = state.cart.items.get(index) {
+                state.current_order = item.order.clone();
+                state.editing = Some(index);
+                state.current_page = Page::Menu;
+            }
             Task::none()
         }
-        Message::SetPage(page) => {
-            state.current_page = page.clone();
-            if let Page::OrderComplete = page {
+        Message::DuplicateItem(index) => {
+            state.cart.duplicate(index);
+            Task::none()
+        }
+        Message::RemoveItem(index) => {
+            state.cart.remove(index);
+            Task::none()
+        }
+        Message::SetQuantity(index, quantity) => {
+            if let Some(item) = state.cart.items.get_mut(index) {
+                item.quantity = quantity.clamp(1, CartItem::MAX_QUANTITY);
+            }
+            Task::none()
+        }
+        Message::EnterPromo => {
+            state.promo_entry = Some(String::new());
+            state.promo_error = None;
+            Task::none()
+        }
+        Message::PromoKey(key) => {
+            if let Some(entry) = &mut state.promo_entry {
+                entry.push(key);
+            }
+            Task::none()
+        }
+        Message::PromoBackspace => {
+            if let Some(entry) = &mut state.promo_entry {
+                entry.pop();
+            }
+            Task::none()
+        }
+        Message::ApplyPromo => {
+            if let Some(code) = state.promo_entry.clone().filter(|code| !code.is_empty()) {
+                state.server.send(&ClientMessage::CheckPromo(code));
+            }
+            Task::none()
+        }
+        Message::CancelPromo => {
+            state.promo_entry = None;
+            state.promo_error = None;
+            Task::none()
+        }
+        Message::RemovePromo => {
+            state.promotion = None;
+            Task::none()
+        }
+        Message::Pay => {
+            state.splitting = false;
+            checkout(state)
+        }
+        Message::Split => {
+            state.splitting = true;
+            state.tenders.clear();
+            state.tender_entry.clear();
+            checkout(state)
+        }
+        Message::PhoneKey(key) => {
+            if state.phone_entry.len() < MAX_PHONE_DIGITS {
+                state.phone_entry.push(key);
+            }
+            Task::none()
+        }
+        Message::PhoneBackspace => {
+            state.phone_entry.pop();
+            Task::none()
+        }
+        Message::CheckLoyalty => {
+            if !state.phone_entry.is_empty() {
+                state
+                    .server
+                    .send(&ClientMessage::CheckLoyalty(state.phone_entry.clone()));
+            }
+            Task::none()
+        }
+        Message::ChooseReward(reward) => {
+            state.reward = Some(reward);
+            Task::none()
+        }
+        Message::RemoveReward => {
+            state.reward = None;
+            Task::none()
+        }
+        Message::ForgetPhone => {
+            state.member = None;
+            state.reward = None;
+            state.phone_entry.clear();
+            Task::none()
+        }
+        Message::SkipLoyalty => {
+            state.collecting_points = false;
+            state.member = None;
+            state.reward = None;
+            state.phone_entry.clear();
+            ask_tip(state)
+        }
+        Message::ContinueCheckout => {
+            state.collecting_points = false;
+            ask_tip(state)
+        }
+        Message::CancelLoyalty => {
+            state.collecting_points = false;
+            state.phone_error = None;
+            state.splitting = false;
+            Task::none()
+        }
+        Message::ChooseTip(tip) => {
+            state.tip = tip;
+            state.choosing_tip = false;
+            state.tip_entry = None;
+            pay(state)
+        }
+        Message::EnterCustomTip => {
+            state.tip_entry = Some(String::new());
+            Task::none()
+        }
+        Message::TipKey(key) => {
+            if let Some(entry) = &mut state.tip_entry {
+                type_digit(entry, key);
+            }
+            Task::none()
+        }
+        Message::TipBackspace => {
+            if let Some(entry) = &mut state.tip_entry {
+                entry.pop();
+            }
+            Task::none()
+        }
+        Message::ApplyCustomTip => match &state.tip_entry {
+            Some(entry) => Task::done(Message::ChooseTip(Some(Tip::Amount {
+                amount: entered_amount(entry),
+            }))),
+            None => Task::none(),
+        },
+        Message::CancelCustomTip => {
+            state.tip_entry = None;
+            Task::none()
+        }
+        Message::CancelTip => {
+            state.choosing_tip = false;
+            state.tip_entry = None;
+            state.splitting = false;
+            Task::none()
+        }
+        Message::TenderKey(key) => {
+            type_digit(&mut state.tender_entry, key);
+            Task::none()
+        }
+        Message::TenderBackspace => {
+            state.tender_entry.pop();
+            Task::none()
+        }
+        Message::AddTender(tender) => {
+            state.tenders.push(tender);
+            state.tender_entry.clear();
+            Task::none()
+        }
+        Message::RemoveTender(index) => {
+            if index < state.tenders.len() {
+                state.tenders.remove(index);
+            }
+            Task::none()
+        }
+        Message::CancelSplit => {
+            state.splitting = false;
+            state.tenders.clear();
+            state.tender_entry.clear();
+            Task::none()
+        }
+        Message::EnterGiftCard => {
+            state.gift_card_entry = Some(String::new());
+            state.gift_card_error = None;
+            Task::none()
+        }
+        Message::GiftCardKey(key) => {
+            if let Some(entry) = &mut state.gift_card_entry {
+                entry.push(key);
+            }
+            Task::none()
+        }
+        Message::GiftCardBackspace => {
+            if let Some(entry) = &mut state.gift_card_entry {
+                entry.pop();
+            }
+            Task::none()
+        }
+        Message::CheckGiftCard => {
+            if let Some(code) = state
+                .gift_card_entry
+                .clone()
+                .filter(|code| !code.is_empty())
+            {
+                state.server.send(&ClientMessage::CheckGiftCard(code));
+            }
+            Task::none()
+        }
+        Message::CancelGiftCard => {
+            state.gift_card_entry = None;
+            state.gift_card_error = None;
+            Task::none()
+        }
+        Message::SubmitOrder => {
+            state.rejection = None;
+            state.paying = true;
+            state.server.send(&ClientMessage::SubmitOrder {
+                cart: state.cart.clone(),
+                promo_code: state
+                    .promotion
+                    .as_ref()
+                    .map(|promotion| promotion.code.clone()),
+                tip: state.tip,
+                tenders: if state.splitting {
+                    state.tenders.clone()
+                } else {
+                    Vec::new()
+                },
+                phone: state.member.as_ref().map(|(phone, _)| phone.clone()),
+                reward: state.reward.as_ref().map(|reward| reward.id),
+            });
+            Task::none()
+        }
+        Message::Server(ServerMessage::OrderAccepted { order_number, .. }) => {
+            state.paying = false;
+            state.cash_due = state
+                .tenders
+                .iter()
+                .filter(|tender| state.splitting && matches!(tender, Tender::Cash { .. }))
+                .map(Tender::amount)
+                .sum();
+            state.order_number = Some(order_number);
+            state.tracked.push(BoardOrder {
+                order_number,
+                status: OrderStatus::Received,
+            });
+            state.current_page = Page::OrderComplete;
+            Task::done(Message::Reset).chain(Task::future(async {
+                sleep(Duration::from_secs(5));
+                Message::SetPage(Page::Menu)
+            }))
+        }
+        Message::Server(ServerMessage::OrderStatusChanged {
+            order_number,
+            status,
+        }) => {
+            if status.is_open() {
+                for order in &mut state.tracked {
+                    if order.order_number == order_number {
+                        order.status = status;
+                    }
+                }
+            } else {
                 state
-                    .ws_server
-                    .send_message(&websocket::Message::text(
-                        json!(state.current_order).to_string(),
-                    ))
-                    .unwrap();
-                Task::done(Message::Reset).chain(Task::future(async {
-                    sleep(Duration::from_secs(5));
-                    Message::SetPage(Page::Menu)
-                }))
+                    .tracked
+                    .retain(|order| order.order_number != order_number);
+            }
+            Task::none()
+        }
+        Message::Server(
+            ServerMessage::OrderRejected { reason } | ServerMessage::PaymentDeclined { reason },
+        ) => {
+            state.paying = false;
+            state.rejection = Some(reason);
+            Task::none()
+        }
+        Message::Server(ServerMessage::MenuUpdated(config)) => {
+            if config.menu.item(state.current_order.item).is_none() {
+                state.current_order = new_order(&config);
+            }
+            state.current_order.reconcile(&config.menu);
+            state.cart.reconcile(&config.menu);
+            let fetch_images = fetch_all_assets(config.clone());
+            state.config = config;
+            recheck_checkout(state);
+            fetch_images.discard()
+        }
+        Message::Server(ServerMessage::PromoAccepted(promotion)) => {
+            state.promotion = Some(promotion);
+            state.promo_entry = None;
+            state.promo_error = None;
+            Task::none()
+        }
+        Message::Server(ServerMessage::PromoRejected { reason, .. }) => {
+            state.promo_error = Some(reason);
+            Task::none()
+        }
+        Message::Server(ServerMessage::GiftCardBalance { code, balance }) => {
+            // Pay as much of the next tender as is left on the card, after
+            // any other tenders already paid from it.
+            let used: Money = state
+                .tenders
+                .iter()
+                .filter(
+                    |tender| matches!(tender, Tender::GiftCard { code: used, .. } if *used == code),
+                )
+                .map(Tender::amount)
+                .sum();
+            let amount = next_tender_amount(state).min(balance - used);
+            if amount > Money::ZERO {
+                state.tenders.push(Tender::GiftCard { code, amount });
+                state.tender_entry.clear();
+                state.gift_card_entry = None;
+                state.gift_card_error = None;
             } else {
-                Task::none()
+                state.gift_card_error = Some(format!("{code} has nothing left on it"));
             }
+            Task::none()
         }
+        Message::Server(ServerMessage::GiftCardRejected { reason, .. }) => {
+            state.gift_card_error = Some(reason);
+            Task::none()
+        }
+        Message::Server(ServerMessage::LoyaltyBalance { phone, points }) => {
+            state.member = Some((phone, points));
+            state.phone_error = None;
+            Task::none()
+        }
+        Message::Server(ServerMessage::LoyaltyRejected { reason, .. }) => {
+            state.phone_error = Some(reason);
+            Task::none()
+        }
+        Message::Server(ServerMessage::Pong) => Task::none(),
         Message::Reset => {
-            state.current_order = state.config.default_order.clone();
+            state.cart = Cart::new();
+            state.current_order = new_order(&state.config);
+            state.editing = None;
+            state.category = 0;
+            state.rejection = None;
+            state.promotion = None;
+            state.promo_entry = None;
+            state.promo_error = None;
+            state.choosing_tip = false;
+            state.tip_entry = None;
+            state.tip = None;
+            state.splitting = false;
+            state.tenders.clear();
+            state.tender_entry.clear();
+            state.gift_card_entry = None;
+            state.gift_card_error = None;
+            state.collecting_points = false;
+            state.phone_entry.clear();
+            state.phone_error = None;
+            state.member = None;
+            state.reward = None;
             Task::none()
         }
     }
 }
 
-fn base_button<'a>(state: &'a State, base: &Base) -> Element<'a, Message> {
-    let image = container(
-        Image::new(format!("assets/{}", base.image_url))
-            .height(Length::FillPortion(3))
-            .width(Length::Fill)
-            .content_fit(ContentFit::Fill),
-    )
-    .padding(10);
+/// Sends the customer back to the cart with what went wrong when a menu
+/// update leaves the order they are checking out unable to be priced, such
+/// as when a required option sells out or their tip is no longer offered.
+fn recheck_checkout(state: &mut State) {
+    if !matches!(state.current_page, Page::Order) || state.paying {
+        return;
+    }
+    if let Err(err) = quote(
+        &state.config,
+        &state.cart,
+        discount(state).as_ref(),
+        state.tip,
+    ) {
+        state.rejection = Some(err.to_string());
+        state.current_page = Page::Cart;
+        state.collecting_points = false;
+        state.choosing_tip = false;
+        state.tip_entry = None;
+        state.tip = None;
+        state.splitting = false;
+        state.tenders.clear();
+        state.tender_entry.clear();
+        state.gift_card_entry = None;
+    }
+}
+
+/// The promo code or reward the order is priced with.
+fn discount(state: &State) -> Option<Promotion> {
+    state
+        .promotion
+        .clone()
+        .or_else(|| state.reward.as_ref().map(Reward::promotion))
+}
+
+/// Whether `reward` takes anything off the cart. The server turns away
+/// orders with rewards that do not.
+fn takes_something_off(state: &State, reward: &Reward) -> bool {
+    quote(&state.config, &state.cart, Some(&reward.promotion()), None)
+        .ok()
+        .and_then(|quote| quote.discount)
+        .is_some_and(|line| line.price < Money::ZERO)
+}
+
+/// Asks for the customer's phone number if the menu config has a loyalty
+/// program, then goes on to the tip.
+fn checkout(state: &mut State) -> Task<Message> {
+    if state.config.loyalty.enabled() {
+        state.collecting_points = true;
+        state.phone_error = None;
+        Task::none()
+    } else {
+        ask_tip(state)
+    }
+}
+
+/// Asks for a tip if the menu config wants one, then goes on to payment.
+fn ask_tip(state: &mut State) -> Task<Message> {
+    if state.config.tip.enabled() {
+        state.choosing_tip = true;
+        state.tip_entry = None;
+        Task::none()
+    } else {
+        pay(state)
+    }
+}
+
+/// Sends the order off to be paid for, unless the customer is splitting it
+/// and has yet to say how.
+fn pay(state: &State) -> Task<Message> {
+    if state.splitting {
+        Task::none()
+    } else {
+        Task::done(Message::SubmitOrder)
+    }
+}
+
+/// The most digits a phone number can have, country code included.
+const MAX_PHONE_DIGITS: usize = 15;
+
+/// How many digits an amount typed on the keypad can have, up to $999.99.
+const MAX_AMOUNT_DIGITS: usize = 5;
+
+/// Adds `key` to an amount being typed, ignoring zeros before the first
+/// digit.
+fn type_digit(entry: &mut String, key: char) {
+    if entry.len() < MAX_AMOUNT_DIGITS && !(entry.is_empty() && key == '0') {
+        entry.push(key);
+    }
+}
+
+/// The amount typed on the keypad, read as cents like a card terminal does.
+fn entered_amount(entry: &str) -> Money {
+    Money::from_cents(entry.parse().unwrap_or(0))
+}
+
+/// How many option buttons fit across a step.
+const OPTIONS_PER_ROW: usize = 4;
 
-    let name = text(base.name.clone())
+fn selected<'a>(
+    content: impl Into<Element<'a, Message>>,
+    is_selected: bool,
+) -> Element<'a, Message> {
+    if is_selected {
+        container(content)
+            .style(|_| container::Style {
+                background: Some(iced::Background::Color(Color::from_rgba(
+                    0.0, 0.0, 0.0, 0.2,
+                ))),
+                ..Default::default()
+            })
+            .into()
+    } else {
+        content.into()
+    }
+}
+
+fn option_button<'a>(state: &'a State, option: &'a ModifierOption) -> Element<'a, Message> {
+    let image: Element<Message> = match &option.image_url {
+        Some(image_url) => container(
+            Image::new(format!("assets/{image_url}"))
+                .height(Length::FillPortion(3))
+                .width(Length::Fill)
+                .content_fit(ContentFit::Contain)
+                .opacity(if option.sold_out { 0.3 } else { 1.0 }),
+        )
+        .padding(10)
+        .into(),
+        None => Space::with_height(FillPortion(3)).into(),
+    };
+
+    let name = text(option.name.clone())
         .font(CHILL_FONT)
         .align_x(Horizontal::Center)
         .size(40);
 
-    let price = text(format!("${:.2}", base.price))
-        .font(CHILL_FONT)
-        .size(40);
+    let quantity = state.current_order.quantity(option.id);
+    let price: Element<Message> = if option.sold_out {
+        text("Sold out").font(CHILL_FONT).size(40).into()
+    } else if option.max_quantity > 1 && quantity > 0 {
+        // Options that can be doubled up get their own - and + once chosen.
+        let step = |icon: Nerd, message: Message| {
+            button(text(icon_to_string(icon)).font(NERD_FONT).size(40))
+                .on_press(message)
+                .style(|_, _| button::Style {
+                    background: None,
+                    ..Default::default()
+                })
+        };
+        row![
+            step(Nerd::Minus, Message::RemoveOption(option.id)),
+            text(quantity.to_string()).font(CHILL_FONT).size(40),
+            step(Nerd::Plus, Message::AddOption(option.id)),
+        ]
+        .spacing(10)
+        .align_y(Vertical::Center)
+        .into()
+    } else if !option.price.is_zero() {
+        text(format!("+{}", option.price))
+            .font(CHILL_FONT)
+            .size(40)
+            .into()
+    } else {
+        Space::with_height(Pixels(40.0)).into()
+    };
 
     let details = column![name, price,]
         .padding(top(10))
@@ -128,7 +753,7 @@ fn base_button<'a>(state: &'a State, base: &Base) -> Element<'a, Message> {
         .align_x(Horizontal::Center);
 
     let button = button(column![image, details])
-        .on_press(Message::SelectBase(base.id))
+        .on_press_maybe((!option.sold_out).then_some(Message::ToggleOption(option.id)))
         .width(Fill)
         .height(Fill)
         .style(|_, _| button::Style {
@@ -136,21 +761,10 @@ fn base_button<'a>(state: &'a State, base: &Base) -> Element<'a, Message> {
             ..Default::default()
         });
 
-    if state.current_order.base == base.id {
-        container(button)
-            .style(|_| container::Style {
-                background: Some(iced::Background::Color(Color::from_rgba(
-                    0.0, 0.0, 0.0, 0.2,
-                ))),
-                ..Default::default()
-            })
-            .into()
-    } else {
-        button.into()
-    }
+    selected(button, state.current_order.is_chosen(option.id))
 }
 
-fn step_header(title: &str, icon: Nerd) -> Element<Message> {
+fn step_header<'a>(title: impl text::IntoFragment<'a>, icon: Nerd) -> Element<'a, Message> {
     container(row![
         text(icon_to_string(icon))
             .font(NERD_FONT)
@@ -173,43 +787,233 @@ fn step_header(title: &str, icon: Nerd) -> Element<Message> {
     .into()
 }
 
-fn base_step_view(state: &State) -> Element<Message> {
-    let header = step_header("Choose your base", Nerd::NumericOneCircle);
+/// The step icon for the `step`th step, counting from 1.
+fn step_icon(step: usize) -> Nerd {
+    match step {
+        1 => Nerd::NumericOneCircle,
+        2 => Nerd::NumericTwoCircle,
+        3 => Nerd::NumericThreeCircle,
+        4 => Nerd::NumericFourCircle,
+        5 => Nerd::NumericFiveCircle,
+        6 => Nerd::NumericSixCircle,
+        7 => Nerd::NumericSevenCircle,
+        8 => Nerd::NumericEightCircle,
+        9 => Nerd::NumericNineCircle,
+        _ => Nerd::NumericNinePlusCircle,
+    }
+}
 
-    let body = row(state
-        .config
-        .menu
-        .bases
+fn pictures_step_body<'a>(state: &'a State, group: &'a ModifierGroup) -> Element<'a, Message> {
+    column(group.options.chunks(OPTIONS_PER_ROW).map(|options| {
+        row(options.iter().map(|option| option_button(state, option)))
+            .height(Fill)
+            .into()
+    }))
+    .height(FillPortion(4))
+    .into()
+}
+
+fn scale_step_body<'a>(state: &'a State, group: &'a ModifierGroup) -> Element<'a, Message> {
+    let chosen = group
+        .options
         .iter()
-        .map(|base| base_button(state, base)))
+        .position(|option| state.current_order.is_chosen(option.id));
+    let image_url = group.image_url.clone().unwrap_or_default();
+
+    let buttons = row(group.options.iter().enumerate().map(|(index, option)| {
+        let image = container(
+            Image::new(format!("assets/{image_url}"))
+                .height(Fill)
+                .width(Length::Fill)
+                .content_fit(ContentFit::ScaleDown)
+                .opacity(if option.sold_out { 0.3 } else { 1.0 }),
+        );
+
+        let button = button(image)
+            .on_press_maybe((!option.sold_out).then_some(Message::ToggleOption(option.id)))
+            .width(Fill)
+            .height(Fill)
+            .style(|_, _| button::Style {
+                background: None,
+                ..Default::default()
+            });
+
+        selected(button, chosen.is_some_and(|chosen| index <= chosen))
+    }))
     .height(FillPortion(4))
     .align_y(Vertical::Center);
 
-    column![header, body].height(Fill).into()
+    let name = chosen
+        .map(|chosen| group.options[chosen].name.clone())
+        .unwrap_or_default();
+
+    row!(
+        container(buttons).width(FillPortion(3)),
+        container(text(name).font(CHILL_FONT).size(40).color(Color::BLACK))
+            .width(FillPortion(2))
+            .padding(20)
+            .height(Fill)
+            .align_y(Vertical::Center)
+    )
+    .height(FillPortion(3))
+    .into()
+}
+
+/// How much of the menu page a group's step takes up, relative to the others.
+fn step_weight(group: &ModifierGroup) -> u16 {
+    match group.style {
+        GroupStyle::Pictures => 1 + 2 * group.options.len().div_ceil(OPTIONS_PER_ROW) as u16,
+        GroupStyle::Scale => 3,
+    }
+}
+
+fn group_step_view<'a>(
+    state: &'a State,
+    step: usize,
+    group: &'a ModifierGroup,
+) -> Element<'a, Message> {
+    let mut title = format!("Choose your {}", group.name.to_lowercase());
+    let allowance = group.allowance(&state.current_order);
+    if let Some(allowance) = allowance.filter(|allowance| !allowance.extra_price.is_zero()) {
+        title += &format!(", {} included", allowance.included);
+    }
+    let header = step_header(title, step_icon(step));
+
+    let body = match group.style {
+        GroupStyle::Pictures => pictures_step_body(state, group),
+        GroupStyle::Scale => scale_step_body(state, group),
+    };
+
+    column![header, body]
+        .height(FillPortion(step_weight(group)))
+        .into()
+}
+
+fn next_button(message: Message) -> Element<'static, Message> {
+    button(
+        text(icon_to_string(Nerd::ChevronRight))
+            .font(NERD_FONT)
+            .size(100)
+            .height(Fill)
+            .width(Fill)
+            .center(),
+    )
+    .on_press(message)
+    .style(|_, _| button::Style {
+        background: Some(iced::Background::Color(Color::from_rgb8(219, 84, 97))),
+        text_color: Color::BLACK,
+        ..Default::default()
+    })
+    .height(Fill)
+    .width(Fill)
+    .into()
+}
+
+fn menu_view(state: &State) -> Element<Message> {
+    let groups = state
+        .config
+        .menu
+        .item(state.current_order.item)
+        .map(|item| &item.modifier_groups[..])
+        .unwrap_or(&[]);
+
+    let mut steps = Vec::new();
+    for (index, group) in groups.iter().enumerate() {
+        if index > 0 {
+            steps.push(horizontal_rule(2).into());
+        }
+        steps.push(group_step_view(state, index + 1, group));
+    }
+
+    row![
+        column![
+            container(row![
+                Space::with_width(Length::FillPortion(1)),
+                Image::new("assets/Logo.png")
+                    .rotation(Rotation::Solid(Radians(-PI / 16.0)))
+                    .width(Length::FillPortion(10)),
+                Space::with_width(Length::FillPortion(1)),
+            ])
+            .height(Length::FillPortion(3))
+            .center(Length::Fill),
+            tracked_orders_view(state),
+            horizontal_rule(2),
+            container(next_button(Message::AddToCart)).height(FillPortion(1))
+        ]
+        .width(Length::FillPortion(1))
+        .height(Length::Fill),
+        vertical_rule(2),
+        column(steps).width(Length::FillPortion(3))
+    ]
+    .into()
+}
+
+/// The status of every order placed here that has not been picked up yet.
+fn tracked_orders_view(state: &State) -> Element<'_, Message> {
+    column(state.tracked.iter().map(|order| {
+        text(format!("Order #{}: {}", order.order_number, order.status))
+            .font(CHILL_FONT)
+            .size(30)
+            .color(Color::BLACK)
+            .into()
+    }))
+    .spacing(10)
+    .padding(20)
+    .into()
+}
+
+fn category_button(state: &State, index: usize, category: &Category) -> Element<'static, Message> {
+    let content = row![
+        Image::new(format!("assets/{}", category.image_url))
+            .content_fit(ContentFit::Contain)
+            .width(FillPortion(1)),
+        text(category.name.clone())
+            .font(CHILL_FONT)
+            .size(50)
+            .color(Color::BLACK)
+            .width(FillPortion(2)),
+    ]
+    .spacing(20)
+    .align_y(Vertical::Center);
+
+    let button = button(content)
+        .on_press(Message::SelectCategory(index))
+        .width(Fill)
+        .height(Fill)
+        .padding(20)
+        .style(|_, _| button::Style {
+            background: None,
+            ..Default::default()
+        });
+
+    selected(button, state.category == index)
 }
 
-fn topping_button<'a>(state: &'a State, topping: &Toppings) -> Element<'a, Message> {
+fn item_button<'a>(state: &'a State, item: &'a MenuItem) -> Element<'a, Message> {
     let image = container(
-        Image::new(format!("assets/{}", topping.image_url))
+        Image::new(format!("assets/{}", item.image_url))
             .height(Length::FillPortion(3))
             .width(Length::Fill)
-            .content_fit(ContentFit::Contain),
+            .content_fit(ContentFit::Contain)
+            .opacity(if item.sold_out { 0.3 } else { 1.0 }),
     )
     .padding(10);
 
-    let name = text(topping.name.clone())
+    let name = text(item.name.clone())
         .font(CHILL_FONT)
         .align_x(Horizontal::Center)
         .size(40);
 
-    let price: Element<Message> = if let Some(price) = topping.price.filter(|p| p > &0.0) {
-        text(format!("${:.2}", price))
-            .font(CHILL_FONT)
-            .size(40)
-            .into()
+    let in_cart = state.cart.count(item.id);
+    let price = if item.sold_out {
+        text("Sold out")
+    } else if in_cart > 0 {
+        text(format!("{} ({in_cart} in cart)", item.price))
     } else {
-        Space::with_height(Pixels(40.0)).into()
-    };
+        text(item.price.to_string())
+    }
+    .font(CHILL_FONT)
+    .size(40);
 
     let details = column![name, price,]
         .padding(top(10))
@@ -217,282 +1021,386 @@ fn topping_button<'a>(state: &'a State, topping: &Toppings) -> Element<'a, Messa
         .width(Fill)
         .align_x(Horizontal::Center);
 
-    let button = button(column![image, details])
-        .on_press(Message::ToggleTopping(topping.id))
+    button(column![image, details])
+        .on_press_maybe((!item.sold_out).then_some(Message::ChooseItem(item.id)))
         .width(Fill)
         .height(Fill)
         .style(|_, _| button::Style {
             background: None,
             ..Default::default()
-        });
-
-    if state.current_order.toppings.contains(&topping.id) {
-        container(button)
-            .style(|_| container::Style {
-                background: Some(iced::Background::Color(Color::from_rgba(
-                    0.0, 0.0, 0.0, 0.2,
-                ))),
-                ..Default::default()
-            })
-            .into()
-    } else {
-        button.into()
-    }
+        })
+        .into()
 }
 
-fn toppings_step_view(state: &State) -> Element<Message> {
-    let header = step_header("Choose your toppings", Nerd::NumericTwoCircle);
-
-    let num_toppings = state.config.menu.toppings.len();
-    let top_toppings = state.config.menu.toppings[..num_toppings / 2].to_vec();
-    let bottom_toppings = state.config.menu.toppings[num_toppings / 2..].to_vec();
-
-    let top_row = row(top_toppings
-        .iter()
-        .map(|topping| topping_button(state, topping)));
-
-    let bottom_row = row(bottom_toppings
-        .iter()
-        .map(|topping| topping_button(state, topping)));
+fn browse_view(state: &State) -> Element<'_, Message> {
+    let categories = column(
+        state
+            .config
+            .menu
+            .categories
+            .iter()
+            .enumerate()
+            .map(|(index, category)| category_button(state, index, category)),
+    );
 
-    let body = column![top_row, bottom_row].height(FillPortion(4));
+    let items = state
+        .config
+        .menu
+        .categories
+        .get(state.category)
+        .map(|category| &category.items[..])
+        .unwrap_or(&[]);
+    let items = column(items.chunks(OPTIONS_PER_ROW).map(|items| {
+        row(items.iter().map(|item| item_button(state, item)))
+            .height(Fill)
+            .into()
+    }));
 
-    column![header, body].height(Fill).into()
+    column![
+        container(step_header("Anything else?", Nerd::SilverwareForkKnife)).height(FillPortion(1)),
+        horizontal_rule(2),
+        row![
+            container(categories).width(FillPortion(1)),
+            vertical_rule(2),
+            container(items).width(FillPortion(3)),
+        ]
+        .height(FillPortion(6)),
+        horizontal_rule(2),
+        row![
+            Space::with_width(FillPortion(5)),
+            container(next_button(Message::SetPage(Page::Cart))).width(FillPortion(1)),
+        ]
+        .height(FillPortion(2)),
+    ]
+    .into()
 }
 
-fn spice_level_button<'a>(state: &'a State, spice_level: &SpiceLevel) -> Element<'a, Message> {
-    let image = container(
-        Image::new(format!("assets/spice.png"))
-            .height(Fill)
-            .width(Length::Fill)
-            .content_fit(ContentFit::ScaleDown),
-    );
+fn cart_item_view<'a>(
+    state: &'a State,
+    index: usize,
+    item: &'a CartItem,
+    quote: Option<&ItemQuote>,
+) -> Element<'a, Message> {
+    let menu_item = state.config.menu.item(item.order.item);
+    let groups = menu_item
+        .map(|menu_item| &menu_item.modifier_groups[..])
+        .unwrap_or(&[]);
+    let has_options = !groups.is_empty();
+
+    let description = column![text(
+        menu_item
+            .map(|menu_item| menu_item.name.clone())
+            .unwrap_or_default()
+    )
+    .font(CHILL_FONT)
+    .size(40)
+    .color(Color::BLACK)]
+    .extend(groups.iter().filter_map(|group| {
+        let names: Vec<String> = item
+            .order
+            .chosen(group)
+            .filter_map(|chosen| {
+                group.option(chosen.id).map(|option| match chosen.quantity {
+                    1 => option.name.clone(),
+                    quantity => format!("{quantity} x {}", option.name),
+                })
+            })
+            .collect();
+        (!names.is_empty()).then(|| text(names.join(", ")).size(24).color(Color::BLACK).into())
+    }))
+    .width(FillPortion(3));
 
-    let button = button(image)
-        .on_press(Message::SelectSpiceLevel(spice_level.level))
-        .width(Fill)
-        .height(Fill)
+    let icon_button = |icon: Nerd, message: Option<Message>| {
+        button(
+            text(icon_to_string(icon))
+                .font(NERD_FONT)
+                .size(50)
+                .color(Color::BLACK)
+                .center(),
+        )
+        .on_press_maybe(message)
         .style(|_, _| button::Style {
             background: None,
             ..Default::default()
-        });
+        })
+    };
 
-    if spice_level.level <= state.current_order.spice_level {
-        container(button)
-            .style(|_| container::Style {
-                background: Some(iced::Background::Color(Color::from_rgba(
-                    0.0, 0.0, 0.0, 0.2,
-                ))),
-                ..Default::default()
-            })
-            .into()
-    } else {
-        button.into()
-    }
-}
+    let quantity = row![
+        icon_button(
+            Nerd::Minus,
+            (item.quantity > 1).then_some(Message::SetQuantity(index, item.quantity - 1))
+        ),
+        text(item.quantity.to_string())
+            .font(CHILL_FONT)
+            .size(50)
+            .color(Color::BLACK),
+        icon_button(
+            Nerd::Plus,
+            (item.quantity < CartItem::MAX_QUANTITY)
+                .then_some(Message::SetQuantity(index, item.quantity + 1))
+        ),
+    ]
+    .spacing(10)
+    .align_y(Vertical::Center)
+    .width(FillPortion(2));
 
-fn spice_level_view(state: &State) -> Element<Message> {
-    let header = step_header("Choose your spice level", Nerd::NumericThreeCircle);
+    let price = text(
+        quote
+            .map(|quote| quote.total.to_string())
+            .unwrap_or_default(),
+    )
+    .font(CHILL_FONT)
+    .size(40)
+    .color(Color::BLACK)
+    .width(FillPortion(1));
+
+    let actions = row![
+        icon_button(
+            Nerd::Pencil,
+            has_options.then_some(Message::EditItem(index))
+        ),
+        icon_button(Nerd::ContentCopy, Some(Message::DuplicateItem(index))),
+        icon_button(Nerd::Delete, Some(Message::RemoveItem(index))),
+    ]
+    .spacing(20)
+    .width(FillPortion(2));
 
-    let buttons = row(state
-        .config
-        .menu
-        .spice_levels
-        .iter()
-        .map(|spice_level| spice_level_button(state, spice_level)))
-    .height(FillPortion(4))
-    .align_y(Vertical::Center);
+    row![description, quantity, price, actions]
+        .align_y(Vertical::Center)
+        .padding(20)
+        .into()
+}
 
-    let spice_level = state
-        .config
-        .menu
-        .spice_levels
-        .iter()
-        .find(|spice_level| spice_level.level == state.current_order.spice_level)
-        .unwrap();
+fn cart_view(state: &State) -> Element<'_, Message> {
+    let quote = quote(&state.config, &state.cart, discount(state).as_ref(), None);
+    let problem = match &quote {
+        Err(err) if !state.cart.is_empty() => Some(err.to_string()),
+        _ => state.rejection.clone(),
+    };
+    let quote = quote.ok();
+    let quoted = quote.as_ref().map(|quote| &quote.items[..]).unwrap_or(&[]);
 
-    let body = row!(
-        container(buttons).width(FillPortion(3)),
+    let items: Element<Message> = if state.cart.is_empty() {
         container(
-            text(spice_level.name.clone())
+            text("Your cart is empty")
                 .font(CHILL_FONT)
-                .size(40)
-                .color(Color::BLACK)
+                .size(60)
+                .color(Color::BLACK),
         )
-        .width(FillPortion(2))
-        .padding(20)
+        .center(Fill)
+        .into()
+    } else {
+        scrollable(column(state.cart.items.iter().enumerate().map(
+            |(index, item)| {
+                column![
+                    cart_item_view(state, index, item, quoted.get(index)),
+                    horizontal_rule(2)
+                ]
+                .into()
+            },
+        )))
         .height(Fill)
-        .align_y(Vertical::Center)
-    )
-    .height(FillPortion(3));
+        .into()
+    };
 
-    column![header, body].height(Fill).into()
-}
+    let wide_button = |content: Element<'static, Message>, message: Option<Message>| {
+        button(container(content).center(Fill))
+            .on_press_maybe(message)
+            .width(Fill)
+            .height(Fill)
+            .style(|_, _| button::Style {
+                background: Some(iced::Background::Color(Color::from_rgb8(219, 84, 97))),
+                text_color: Color::BLACK,
+                ..Default::default()
+            })
+    };
 
-fn next_button(state: &State) -> Element<Message> {
-    button(
+    let add_more = wide_button(
+        text("Add more")
+            .font(CHILL_FONT)
+            .size(50)
+            .color(Color::BLACK)
+            .into(),
+        Some(Message::SetPage(Page::Browse)),
+    );
+    let subtotal = text(
+        quote
+            .as_ref()
+            .map(|quote| format!("Subtotal: {}", quote.subtotal))
+            .unwrap_or_default(),
+    )
+    .font(CHILL_FONT)
+    .size(50)
+    .color(Color::BLACK);
+    let subtotal = column![subtotal]
+        .push_maybe(problem.map(error_text))
+        .align_x(Horizontal::Center);
+    let checkout = wide_button(
         text(icon_to_string(Nerd::ChevronRight))
             .font(NERD_FONT)
             .size(100)
-            .height(Fill)
-            .width(Fill)
-            .center(),
-    )
-    .on_press(Message::SetPage(Page::Order))
-    .style(|_, _| button::Style {
-        background: Some(iced::Background::Color(Color::from_rgb8(219, 84, 97))),
-        text_color: Color::BLACK,
-        ..Default::default()
-    })
-    .height(Fill)
-    .width(Fill)
-    .into()
-}
+            .into(),
+        quote.is_some().then_some(Message::SetPage(Page::Order)),
+    );
 
-fn menu_view(state: &State) -> Element<Message> {
-    row![
-        column![
-            container(row![
-                Space::with_width(Length::FillPortion(1)),
-                Image::new("assets/Logo.png")
-                    .rotation(Rotation::Solid(Radians(-PI / 16.0)))
-                    .width(Length::FillPortion(10)),
-                Space::with_width(Length::FillPortion(1)),
-            ])
-            .height(Length::Fill)
-            .center(Length::Fill),
-            horizontal_rule(2),
-            base_step_view(&state)
-        ]
-        .width(Length::FillPortion(1))
-        .height(Length::Fill),
-        vertical_rule(2),
-        column![
-            container(toppings_step_view(&state)).height(FillPortion(3)),
-            horizontal_rule(2),
-            row![
-                container(spice_level_view(&state)).width(FillPortion(5)),
-                vertical_rule(2),
-                container(next_button(&state)).width(FillPortion(1))
-            ]
-            .height(FillPortion(2))
+    column![
+        container(step_header("Your Cart", Nerd::Cart)).height(FillPortion(1)),
+        horizontal_rule(2),
+        container(items).height(FillPortion(6)),
+        horizontal_rule(2),
+        row![
+            container(add_more).width(FillPortion(2)),
+            container(subtotal).center(Fill).width(FillPortion(3)),
+            container(checkout).width(FillPortion(1)),
         ]
+        .height(FillPortion(2)),
     ]
     .into()
 }
 
-fn preview_order(state: &State) -> Element<Message> {
-    let base = state
-        .config
-        .menu
-        .bases
-        .iter()
-        .find(|base| base.id == state.current_order.base)
-        .unwrap();
-
-    let base_element = text(base.name.clone())
-        .font(CHILL_FONT)
-        .size(80)
-        .color(Color::BLACK)
-        .align_x(Horizontal::Center);
+/// Pictures of an order's options, with the name of each single choice
+/// written above them.
+fn preview_options<'a>(menu: &'a Menu, order: &Order, text_size: f32) -> Element<'a, Message> {
+    let groups = menu
+        .item(order.item)
+        .map(|item| &item.modifier_groups[..])
+        .unwrap_or(&[]);
 
-    let toppings: Vec<&Toppings> = state
-        .current_order
-        .toppings
+    let names = groups
         .iter()
-        .map(|topping| {
-            state
-                .config
-                .menu
-                .toppings
-                .iter()
-                .find(|t| t.id == *topping)
-                .unwrap()
+        .filter(|group| group.is_single_choice() && group.style == GroupStyle::Pictures)
+        .flat_map(|group| {
+            order
+                .chosen(group)
+                .filter_map(move |chosen| group.option(chosen.id))
         })
-        .collect();
-    let num_toppings = toppings.len();
-
-    let toppings_element: Element<Message> = if num_toppings > 4 {
-        let top_row = row(toppings[..num_toppings / 2]
-            .iter()
-            .map(|topping| Image::new(format!("assets/{}", topping.image_url)).into()));
-
-        let bottom_row = row(toppings[num_toppings / 2..]
-            .iter()
-            .map(|topping| Image::new(format!("assets/{}", topping.image_url)).into()));
+        .map(|option| {
+            text(option.name.clone())
+                .font(CHILL_FONT)
+                .size(text_size)
+                .color(Color::BLACK)
+                .align_x(Horizontal::Center)
+                .into()
+        });
 
-        column!(top_row, bottom_row).into()
-    } else {
-        row(toppings
-            .iter()
-            .map(|topping| Image::new(format!("assets/{}", topping.image_url)).into()))
-        .into()
-    };
+    let pictures = groups
+        .iter()
+        .filter(|group| !group.is_single_choice() && group.style == GroupStyle::Pictures)
+        .map(|group| {
+            // Extra helpings show up as extra pictures.
+            let images: Vec<&String> = order
+                .chosen(group)
+                .filter_map(|chosen| {
+                    let option = group.option(chosen.id)?;
+                    let image_url = option.image_url.as_ref()?;
+                    Some(std::iter::repeat_n(image_url, chosen.quantity as usize))
+                })
+                .flatten()
+                .collect();
+            let image_row = |images: &[&String]| {
+                row(images
+                    .iter()
+                    .map(|image_url| Image::new(format!("assets/{image_url}")).into()))
+            };
+
+            if images.len() > 4 {
+                let (top, bottom) = images.split_at(images.len() / 2);
+                column!(image_row(top), image_row(bottom)).into()
+            } else {
+                image_row(&images).into()
+            }
+        });
 
-    let spice_level_element = row((0..=state.current_order.spice_level).map(|_| {
-        Image::new("assets/spice.png")
-            .content_fit(ContentFit::Contain)
+    let scales = groups
+        .iter()
+        .filter(|group| group.style == GroupStyle::Scale)
+        .map(|group| {
+            let steps = group
+                .options
+                .iter()
+                .position(|option| order.is_chosen(option.id))
+                .map_or(0, |chosen| chosen + 1);
+            let image_url = group.image_url.clone().unwrap_or_default();
+            row((0..steps).map(|_| {
+                Image::new(format!("assets/{image_url}"))
+                    .content_fit(ContentFit::Contain)
+                    .into()
+            }))
             .into()
-    }));
+        });
 
-    column![base_element, toppings_element, spice_level_element,]
+    column(names.chain(pictures).chain(scales))
         .align_x(Horizontal::Center)
         .into()
 }
 
 fn order_summary_view(state: &State) -> Element<Message> {
-    let base = state
-        .config
-        .menu
-        .bases
-        .iter()
-        .find(|base| base.id == state.current_order.base)
-        .unwrap();
+    let quote = match quote(
+        &state.config,
+        &state.cart,
+        discount(state).as_ref(),
+        state.tip,
+    ) {
+        Ok(quote) => quote,
+        Err(err) => return error_text(format!("Sorry, this order can't be placed: {err}")),
+    };
 
-    let base_item = (base.name.clone(), base.price);
+    let line = |content: String| {
+        text(content)
+            .font(CHILL_FONT)
+            .size(40)
+            .width(Fill)
+            .align_x(Horizontal::Right)
+            .color(Color::BLACK)
+            .into()
+    };
 
-    let topping_items = state
-        .current_order
-        .toppings
+    let subtotal = (quote.discount.is_some() || !quote.taxes.is_empty())
+        .then(|| format!("Subtotal: {}", quote.subtotal));
+    let discount = quote
+        .discount
+        .as_ref()
+        .map(|line| format!("{}: {}", line.name, line.price));
+    let taxes: Vec<String> = quote
+        .taxes
         .iter()
-        .filter_map(|topping_id| {
-            let topping = state
-                .config
-                .menu
-                .toppings
-                .iter()
-                .find(|t| t.id == *topping_id)
-                .unwrap();
-            if let Some(price) = topping.price.filter(|p| p > &0.0) {
-                Some((topping.name.clone(), price))
-            } else {
-                None
-            }
-        });
-
-    let items = vec![base_item].into_iter().chain(topping_items);
-    let total = items.clone().fold(0.0, |acc, (_, price)| acc + price);
+        .map(|tax| {
+            let included = if quote.tax_inclusive { "Includes " } else { "" };
+            format!("{included}{} ({}): {}", tax.name, tax.percent, tax.amount)
+        })
+        .collect();
+    let tip = (!quote.tip.is_zero()).then(|| format!("Tip: {}", quote.tip));
+    let total = quote.total;
+
+    let items = quote.items.into_iter().flat_map(|item| {
+        let mut lines = item.lines.into_iter();
+        let name = lines.next().map(|line| line.name).unwrap_or_default();
+        let options = lines
+            .filter(|line| !line.price.is_zero())
+            .map(|line| match line.quantity {
+                1 => format!("+ {} - {}", line.name, line.price),
+                quantity => format!("+ {quantity} x {} - {}", line.name, line.total()),
+            });
+        std::iter::once(format!("{} x {name} - {}", item.quantity, item.total)).chain(options)
+    });
 
     container(
         column(
             items
-                .map(|(name, price)| {
-                    text(format!("{name} - ${:.2}", price))
-                        .font(CHILL_FONT)
-                        .size(40)
-                        .width(Fill)
-                        .align_x(Horizontal::Right)
-                        .color(Color::BLACK)
-                        .into()
-                })
+                .map(line)
                 .chain(vec![horizontal_rule(2).into()])
-                .chain(vec![text(format!("Total: ${:.2}", total))
-                    .font(CHILL_FONT)
-                    .size(40)
-                    .width(Fill)
-                    .align_x(Horizontal::Right)
-                    .color(Color::BLACK)
-                    .into()]),
+                .chain(
+                    subtotal
+                        .into_iter()
+                        .chain(discount)
+                        .chain(taxes)
+                        .chain(tip)
+                        .map(line),
+                )
+                .chain(vec![line(format!("Total: {total}"))])
+                .chain(state.rejection.iter().map(|reason| {
+                    error_text(format!("Sorry, we couldn't take this order: {reason}"))
+                })),
         )
         .width(Fill)
         .height(Shrink)
@@ -504,7 +1412,35 @@ fn order_summary_view(state: &State) -> Element<Message> {
     .into()
 }
 
-fn pay_view() -> Element<'static, Message> {
+fn error_text(message: String) -> Element<'static, Message> {
+    text(message)
+        .size(24)
+        .width(Fill)
+        .align_x(Horizontal::Right)
+        .color(Color::from_rgb8(219, 84, 97))
+        .into()
+}
+
+fn pay_view(paying: bool) -> Element<'static, Message> {
+    if paying {
+        return container(
+            column![
+                text(icon_to_string(Nerd::CreditCard))
+                    .font(NERD_FONT)
+                    .size(60)
+                    .color(Color::BLACK),
+                text("Please pay at the terminal")
+                    .font(CHILL_FONT)
+                    .size(40)
+                    .color(Color::BLACK)
+                    .align_x(Horizontal::Center),
+            ]
+            .align_x(Horizontal::Center),
+        )
+        .center(Fill)
+        .into();
+    }
+
     button(row![
         column![
             Image::new("assets/applepay.png")
@@ -525,36 +1461,466 @@ fn pay_view() -> Element<'static, Message> {
             .align_x(Horizontal::Center)
             .align_y(Vertical::Center),
     ])
-    .on_press(Message::SetPage(Page::OrderComplete))
+    .on_press(Message::Pay)
     .into()
 }
 
 fn order_view(state: &State) -> Element<Message> {
-    let order_preview = column![
-        container(step_header("Your Order", Nerd::NumericFourCircle)).height(FillPortion(1)),
-        horizontal_rule(2),
+    // Names shrink as more items share the screen.
+    let text_size = (80.0 / state.cart.items.len().max(1) as f32).max(30.0);
+    let items = row(state.cart.items.iter().map(|item| {
+        let image_url = state
+            .config
+            .menu
+            .item(item.order.item)
+            .map(|menu_item| menu_item.image_url.clone())
+            .unwrap_or_default();
         stack!(
-            Image::new("assets/bowl.png")
+            Image::new(format!("assets/{image_url}"))
                 .content_fit(ContentFit::Contain)
                 .width(Fill)
                 .height(Fill),
-            container(preview_order(state)).center(Fill)
+            container(preview_options(&state.config.menu, &item.order, text_size)).center(Fill)
         )
-        .height(FillPortion(8))
-    ]
-    .width(FillPortion(3));
+        .into()
+    }));
+
+    let order_preview = match (&state.promo_entry, &state.gift_card_entry) {
+        (Some(entry), _) => promo_entry_view(state, entry),
+        (_, Some(entry)) => gift_card_entry_view(state, entry),
+        _ if state.collecting_points => loyalty_view(state),
+        _ if state.choosing_tip => tip_view(state),
+        _ if state.splitting => split_view(state),
+        _ => column![
+            container(step_header("Your Order", Nerd::CreditCard)).height(FillPortion(1)),
+            horizontal_rule(2),
+            container(items).height(FillPortion(8))
+        ]
+        .into(),
+    };
+
+    let text_button = |label: String, message: Message| {
+        button(
+            text(label)
+                .font(CHILL_FONT)
+                .size(40)
+                .color(Color::BLACK)
+                .center(),
+        )
+        .on_press_maybe((!state.paying).then_some(message))
+        .width(Fill)
+        .height(Fill)
+        .style(|_, _| button::Style {
+            background: None,
+            ..Default::default()
+        })
+    };
+    let promo_button = match (&state.promotion, &state.reward) {
+        (Some(promotion), _) => {
+            text_button(format!("Remove {}", promotion.code), Message::RemovePromo)
+        }
+        (None, Some(reward)) => {
+            text_button(format!("Remove {}", reward.name), Message::RemoveReward)
+        }
+        (None, None) => text_button("Promo code".to_string(), Message::EnterPromo),
+    };
+    let split_button = text_button("Split payment".to_string(), Message::Split);
 
     let order_summary_and_pay = column![
         container(order_summary_view(state)).height(FillPortion(6)),
-        container(pay_view()).height(Fill)
+        container(row![promo_button, split_button]).height(FillPortion(1)),
+        container(pay_view(state.paying)).height(Fill)
     ]
     .width(FillPortion(1))
     .height(Fill);
 
-    row![order_preview, vertical_rule(2), order_summary_and_pay,].into()
+    row![
+        container(order_preview).width(FillPortion(3)),
+        vertical_rule(2),
+        order_summary_and_pay,
+    ]
+    .into()
+}
+
+/// A big button for the steps on the order page, greyed out without a
+/// `message`.
+fn wide_button<'a>(
+    label: impl text::IntoFragment<'a>,
+    message: impl Into<Option<Message>>,
+) -> Element<'a, Message> {
+    button(
+        text(label)
+            .font(CHILL_FONT)
+            .size(50)
+            .color(Color::BLACK)
+            .center(),
+    )
+    .on_press_maybe(message.into())
+    .width(Fill)
+    .height(Fill)
+    .style(|_, status| button::Style {
+        background: Some(iced::Background::Color(match status {
+            button::Status::Disabled => Color::from_rgba8(219, 84, 97, 0.3),
+            _ => Color::from_rgb8(219, 84, 97),
+        })),
+        text_color: Color::BLACK,
+        ..Default::default()
+    })
+    .into()
+}
+
+/// What the keys and buttons of a [`code_entry_view`] send.
+struct CodeEntryMessages {
+    key: fn(char) -> Message,
+    backspace: Message,
+    cancel: Message,
+    apply: Message,
+}
+
+/// A code being typed on the on-screen keyboard, such as a promo code.
+fn code_entry_view<'a>(
+    title: &'a str,
+    icon: Nerd,
+    entry: &str,
+    error: Option<String>,
+    messages: CodeEntryMessages,
+) -> Element<'a, Message> {
+    let entered = text(entry.to_string())
+        .font(CHILL_FONT)
+        .size(80)
+        .color(Color::BLACK)
+        .center();
+    let error = text(error.unwrap_or_default())
+        .size(24)
+        .color(Color::from_rgb8(219, 84, 97))
+        .center();
+
+    column![
+        container(step_header(title, icon)).height(FillPortion(1)),
+        horizontal_rule(2),
+        container(column![entered, error].align_x(Horizontal::Center))
+            .center(Fill)
+            .height(FillPortion(2)),
+        container(keyboard::keyboard(messages.key, messages.backspace))
+            .padding(20)
+            .height(FillPortion(5)),
+        row![
+            wide_button("Cancel", messages.cancel),
+            wide_button("Apply", messages.apply),
+        ]
+        .spacing(20)
+        .padding(20)
+        .height(FillPortion(1)),
+    ]
+    .into()
+}
+
+fn promo_entry_view<'a>(state: &'a State, entry: &str) -> Element<'a, Message> {
+    code_entry_view(
+        "Promo Code",
+        Nerd::Tag,
+        entry,
+        state.promo_error.clone(),
+        CodeEntryMessages {
+            key: Message::PromoKey,
+            backspace: Message::PromoBackspace,
+            cancel: Message::CancelPromo,
+            apply: Message::ApplyPromo,
+        },
+    )
+}
+
+fn gift_card_entry_view<'a>(state: &'a State, entry: &str) -> Element<'a, Message> {
+    code_entry_view(
+        "Gift Card",
+        Nerd::Gift,
+        entry,
+        state.gift_card_error.clone(),
+        CodeEntryMessages {
+            key: Message::GiftCardKey,
+            backspace: Message::GiftCardBackspace,
+            cancel: Message::CancelGiftCard,
+            apply: Message::CheckGiftCard,
+        },
+    )
+}
+
+/// Asks the customer for their phone number on the keypad, then shows their
+/// points and lets them spend some on a reward.
+fn loyalty_view(state: &State) -> Element<'_, Message> {
+    let error = text(state.phone_error.clone().unwrap_or_default())
+        .size(24)
+        .color(Color::from_rgb8(219, 84, 97))
+        .center();
+
+    let Some((_, points)) = &state.member else {
+        let entered = text(state.phone_entry.clone())
+            .font(CHILL_FONT)
+            .size(80)
+            .color(Color::BLACK)
+            .center();
+        return column![
+            container(step_header("Collect Points", Nerd::Phone)).height(FillPortion(1)),
+            horizontal_rule(2),
+            container(column![entered, error].align_x(Horizontal::Center))
+                .center(Fill)
+                .height(FillPortion(2)),
+            container(keyboard::keypad(Message::PhoneKey, Message::PhoneBackspace))
+                .padding(20)
+                .height(FillPortion(5)),
+            row![
+                wide_button("Cancel", Message::CancelLoyalty),
+                wide_button("Skip", Message::SkipLoyalty),
+                wide_button("Look up", Message::CheckLoyalty),
+            ]
+            .spacing(20)
+            .padding(20)
+            .height(FillPortion(1)),
+        ]
+        .into();
+    };
+
+    let earned = quote(&state.config, &state.cart, discount(state).as_ref(), None)
+        .map(|quote| state.config.loyalty.points(&quote))
+        .unwrap_or_default();
+    let line = |content: String, size| {
+        text(content)
+            .font(CHILL_FONT)
+            .size(size)
+            .color(Color::BLACK)
+            .center()
+    };
+    let note = if state.promotion.is_some() {
+        "Rewards cannot be used with a promo code".to_string()
+    } else {
+        format!("This order earns {earned} more")
+    };
+
+    let rewards = state.config.loyalty.rewards.iter().map(|reward| {
+        let chosen = state.reward.as_ref() == Some(reward);
+        let message = if chosen {
+            Some(Message::RemoveReward)
+        } else {
+            (state.promotion.is_none()
+                && *points >= i64::from(reward.points)
+                && takes_something_off(state, reward))
+            .then(|| Message::ChooseReward(reward.clone()))
+        };
+        let check = if chosen { "✓ " } else { "" };
+        wide_button(
+            format!("{check}{}\n{} points", reward.name, reward.points),
+            message,
+        )
+    });
+
+    column![
+        container(step_header("Your Points", Nerd::Star)).height(FillPortion(1)),
+        horizontal_rule(2),
+        container(
+            column![line(format!("{points} points"), 80), line(note, 32)]
+                .align_x(Horizontal::Center)
+        )
+        .center(Fill)
+        .height(FillPortion(2)),
+        container(row(rewards).spacing(20).padding(20))
+            .center(Fill)
+            .height(FillPortion(4)),
+        row![
+            wide_button("Not me", Message::ForgetPhone),
+            wide_button("Continue", Message::ContinueCheckout),
+        ]
+        .spacing(20)
+        .padding(20)
+        .height(FillPortion(1)),
+    ]
+    .into()
+}
+
+/// Asks the customer for a tip, offering the configured percentages with
+/// what each comes to, an amount of their own and no tip at all.
+fn tip_view(state: &State) -> Element<'_, Message> {
+    let tip_config = &state.config.tip;
+
+    if let Some(entry) = &state.tip_entry {
+        return column![
+            container(step_header("Your Tip", Nerd::Cash)).height(FillPortion(1)),
+            horizontal_rule(2),
+            container(
+                text(entered_amount(entry).to_string())
+                    .font(CHILL_FONT)
+                    .size(80)
+                    .color(Color::BLACK)
+            )
+            .center(Fill)
+            .height(FillPortion(2)),
+            container(keyboard::keypad(Message::TipKey, Message::TipBackspace))
+                .padding(20)
+                .height(FillPortion(5)),
+            row![
+                wide_button("Back", Message::CancelCustomTip),
+                wide_button("Add tip", Message::ApplyCustomTip),
+            ]
+            .spacing(20)
+            .padding(20)
+            .height(FillPortion(1)),
+        ]
+        .into();
+    }
+
+    let presets = tip_config.presets.iter().map(|&percent| {
+        let tip = Tip::Percent { percent };
+        let amount = quote(
+            &state.config,
+            &state.cart,
+            discount(state).as_ref(),
+            Some(tip),
+        )
+        .map(|quote| quote.tip.to_string())
+        .unwrap_or_default();
+        wide_button(
+            format!("{percent}\n{amount}"),
+            Message::ChooseTip(Some(tip)),
+        )
+    });
+    let custom = tip_config
+        .custom
+        .then(|| wide_button("Other amount", Message::EnterCustomTip));
+
+    column![
+        container(step_header("Add a Tip?", Nerd::Cash)).height(FillPortion(1)),
+        horizontal_rule(2),
+        container(row(presets.chain(custom)).spacing(20).padding(20))
+            .center(Fill)
+            .height(FillPortion(6)),
+        row![
+            wide_button("Cancel", Message::CancelTip),
+            wide_button("No tip", Message::ChooseTip(None)),
+        ]
+        .spacing(20)
+        .padding(20)
+        .height(FillPortion(2)),
+    ]
+    .into()
+}
+
+/// What is left to pay of a split order after the tenders added so far.
+fn left_to_pay(state: &State) -> Money {
+    let total = quote(
+        &state.config,
+        &state.cart,
+        discount(state).as_ref(),
+        state.tip,
+    )
+    .map_or(Money::ZERO, |quote| quote.total);
+    total - state.tenders.iter().map(Tender::amount).sum::<Money>()
+}
+
+/// What the next tender of a split order is for: the amount typed on the
+/// keypad, or whatever is left to pay.
+fn next_tender_amount(state: &State) -> Money {
+    let remaining = left_to_pay(state);
+    match state.tender_entry.as_str() {
+        "" => remaining,
+        entry => entered_amount(entry).min(remaining),
+    }
+}
+
+/// Lets the customer split the order between cards, cash at the counter and
+/// gift cards, typing each amount on the keypad or leaving it blank for
+/// whatever is left to pay.
+fn split_view(state: &State) -> Element<'_, Message> {
+    let remaining = left_to_pay(state);
+    let amount = next_tender_amount(state);
+    let add = |tender: Tender| (amount > Money::ZERO).then_some(Message::AddTender(tender));
+
+    let line = |content: String| text(content).font(CHILL_FONT).size(40).color(Color::BLACK);
+    let tenders = state.tenders.iter().enumerate().map(|(index, tender)| {
+        let name = match tender {
+            Tender::Card { .. } => "Card".to_string(),
+            Tender::Cash { .. } => "Cash at the counter".to_string(),
+            Tender::GiftCard { code, .. } => format!("Gift card {code}"),
+        };
+        row![
+            line(format!("{name}: {}", tender.amount())).width(Fill),
+            button(
+                text(icon_to_string(Nerd::Close))
+                    .font(NERD_FONT)
+                    .size(40)
+                    .color(Color::BLACK)
+            )
+            .on_press(Message::RemoveTender(index))
+            .style(|_, _| button::Style {
+                background: None,
+                ..Default::default()
+            }),
+        ]
+        .into()
+    });
+
+    column![
+        container(step_header("Split Payment", Nerd::CreditCard)).height(FillPortion(1)),
+        horizontal_rule(2),
+        row![
+            column(tenders)
+                .push(line(format!("Left to pay: {remaining}")))
+                .spacing(10)
+                .padding(20)
+                .width(Fill),
+            column![
+                container(line(amount.to_string()).size(80)).center_x(Fill),
+                keyboard::keypad(Message::TenderKey, Message::TenderBackspace),
+            ]
+            .spacing(20)
+            .padding(20)
+            .width(Fill),
+        ]
+        .height(FillPortion(6)),
+        row![
+            wide_button("Card", add(Tender::Card { amount })),
+            wide_button("Cash at the counter", add(Tender::Cash { amount })),
+            wide_button(
+                "Gift card",
+                (amount > Money::ZERO).then_some(Message::EnterGiftCard)
+            ),
+        ]
+        .spacing(20)
+        .padding(20)
+        .height(FillPortion(1)),
+        row![
+            wide_button("Cancel", Message::CancelSplit),
+            wide_button(
+                "Pay",
+                (remaining.is_zero() && !state.paying).then_some(Message::SubmitOrder)
+            ),
+        ]
+        .spacing(20)
+        .padding(20)
+        .height(FillPortion(1)),
+    ]
+    .into()
 }
 
-fn order_complete_view() -> Element<'static, Message> {
+fn order_complete_view(state: &State) -> Element<Message> {
+    let order_number = state
+        .order_number
+        .map(|order_number| format!("Your order number is {order_number}"))
+        .unwrap_or_default();
+    let order_status = state
+        .order_number
+        .and_then(|order_number| {
+            state
+                .tracked
+                .iter()
+                .find(|order| order.order_number == order_number)
+        })
+        .map(|order| order.status.to_string())
+        .unwrap_or_default();
+    let cash_due = if state.cash_due.is_zero() {
+        String::new()
+    } else {
+        format!("Please pay {} at the counter", state.cash_due)
+    };
+
     container(
         column![
             container(Image::new("assets/Logo.png").rotation(Rotation::Solid(Radians(-PI / 16.0)))),
@@ -565,6 +1931,27 @@ fn order_complete_view() -> Element<'static, Message> {
                     .size(80)
                     .color(Color::BLACK)
                     .align_x(Horizontal::Center)
+            ),
+            container(
+                text(order_number)
+                    .font(CHILL_FONT)
+                    .size(60)
+                    .color(Color::BLACK)
+                    .align_x(Horizontal::Center)
+            ),
+            container(
+                text(order_status)
+                    .font(CHILL_FONT)
+                    .size(40)
+                    .color(Color::BLACK)
+                    .align_x(Horizontal::Center)
+            ),
+            container(
+                text(cash_due)
+                    .font(CHILL_FONT)
+                    .size(40)
+                    .color(Color::BLACK)
+                    .align_x(Horizontal::Center)
             )
         ]
         .height(Fill)
@@ -577,13 +1964,35 @@ fn order_complete_view() -> Element<'static, Message> {
     .into()
 }
 
+/// Shown while nothing on the menu is scheduled, such as outside opening
+/// hours.
+fn closed_view() -> Element<'static, Message> {
+    container(
+        column![
+            container(Image::new("assets/Logo.png").rotation(Rotation::Solid(Radians(-PI / 16.0)))),
+            text("Sorry, we're not taking orders right now")
+                .font(CHILL_FONT)
+                .size(80)
+                .color(Color::BLACK)
+                .align_x(Horizontal::Center),
+        ]
+        .align_x(Horizontal::Center),
+    )
+    .center(Fill)
+    .into()
+}
+
 fn view(state: &State) -> Element<Message> {
+    let open = state.config.menu.items().next().is_some();
     stack!(
         Image::new("assets/background.png").content_fit(ContentFit::Fill),
         match state.current_page {
+            Page::OrderComplete => order_complete_view(state),
+            _ if !open => closed_view(),
             Page::Menu => menu_view(state),
+            Page::Browse => browse_view(state),
+            Page::Cart => cart_view(state),
             Page::Order => order_view(state),
-            Page::OrderComplete => order_complete_view(),
         }
     )
     .into()
@@ -606,22 +2015,32 @@ fn fetch_all_assets(config: Config) -> Task<()> {
         .map(|entry| entry.unwrap().file_name().into_string().unwrap())
         .collect();
 
-    for topping in config.menu.toppings {
-        if !existing_assets.contains(&topping.image_url) {
-            requests.push(fetch_asset(topping.image_url.clone()));
+    for category in &config.menu.categories {
+        if !existing_assets.contains(&category.image_url) {
+            requests.push(fetch_asset(category.image_url.clone()));
         }
     }
 
-    for base in config.menu.bases {
-        if !existing_assets.contains(&base.image_url) {
-            requests.push(fetch_asset(base.image_url.clone()));
+    for item in config.menu.items() {
+        let group_images = item
+            .modifier_groups
+            .iter()
+            .filter_map(|group| group.image_url.clone());
+        let option_images = item.options().filter_map(|option| option.image_url.clone());
+        let images: Vec<String> = std::iter::once(item.image_url.clone())
+            .chain(group_images)
+            .chain(option_images)
+            .collect();
+        for image_url in images {
+            if !existing_assets.contains(&image_url) {
+                requests.push(fetch_asset(image_url));
+            }
         }
     }
 
     let final_assets = vec![
         "Logo.png",
         "background.png",
-        "spice.png",
         "bowl.png",
         "applepay.png",
         "googlepay.png",
@@ -641,52 +2060,94 @@ fn fetch_all_assets(config: Config) -> Task<()> {
     }
 }
 
-fn main() -> iced::Result {
-    let mut ws_server = websocket::ClientBuilder::new("ws://127.0.0.1:8000/kiosk")
-        .unwrap()
-        .connect(None)
-        .unwrap();
+fn settings() -> Settings {
+    Settings {
+        fonts: vec![
+            Cow::Borrowed(include_bytes!("../assets/ChillScript.ttf")),
+            Cow::Borrowed(NERD_FONT_BYTES),
+        ],
+        antialiasing: true,
+        ..Default::default()
+    }
+}
 
-    ws_server
-        .send_message(&websocket::Message::text("config"))
-        .unwrap();
+fn theme<S>(_: &S) -> Theme {
+    let mut palette = Palette::LIGHT;
+    palette.background = Color::TRANSPARENT;
+    Theme::custom("CalmRam Theme".to_string(), palette)
+}
+
+fn fullscreen() -> Task<()> {
+    iced::window::get_latest()
+        .map(|id| change_mode::<()>(id.unwrap(), Mode::Fullscreen))
+        .discard()
+}
+
+/// A fresh order to start building: the first item on the menu with its
+/// default options, less anything that is sold out.
+fn new_order(config: &Config) -> Order {
+    let first_item = config.menu.items().next().map(|item| item.id);
+    config.menu.new_order(first_item.unwrap_or_default())
+}
 
-    let config: Config = match ws_server.recv_message().unwrap() {
-        websocket::OwnedMessage::Text(text) => serde_json::from_str(&text).unwrap(),
-        _ => panic!("Unexpected message type"),
+fn run_kiosk() -> iced::Result {
+    let (mut server, mut server_messages) = Server::connect::<ServerMessage>("kiosk");
+    server.send(&ClientMessage::GetConfig);
+    let config = match block_on(server_messages.next()) {
+        Some(ServerMessage::MenuUpdated(config)) => config,
+        message => panic!("Expected the menu from the server, got {:?}", message),
     };
 
     iced::application("CalmRam Client", update, view)
-        .settings(Settings {
-            fonts: vec![
-                Cow::Borrowed(include_bytes!("../assets/ChillScript.ttf")),
-                Cow::Borrowed(NERD_FONT_BYTES),
-            ],
-            antialiasing: true,
-            ..Default::default()
-        })
-        .theme(|_| {
-            let mut palette = Palette::LIGHT;
-            palette.background = Color::TRANSPARENT;
-            Theme::custom("CalmRam Theme".to_string(), palette)
-        })
+        .settings(settings())
+        .theme(theme)
         .run_with(move || {
             let fetch_images = fetch_all_assets(config.clone());
-            let window_id = iced::window::get_latest();
 
-            let current_order = config.default_order.clone();
+            let current_order = new_order(&config);
             (
                 State {
                     config,
+                    cart: Cart::new(),
                     current_order,
+                    editing: None,
+                    category: 0,
                     current_page: Page::Menu,
-                    ws_server,
+                    order_number: None,
+                    tracked: Vec::new(),
+                    rejection: None,
+                    paying: false,
+                    promotion: None,
+                    promo_entry: None,
+                    promo_error: None,
+                    choosing_tip: false,
+                    tip_entry: None,
+                    tip: None,
+                    splitting: false,
+                    tenders: Vec::new(),
+                    tender_entry: String::new(),
+                    gift_card_entry: None,
+                    gift_card_error: None,
+                    collecting_points: false,
+                    phone_entry: String::new(),
+                    phone_error: None,
+                    member: None,
+                    reward: None,
+                    cash_due: Money::ZERO,
+                    server,
                 },
-                fetch_images
-                    .discard()
-                    .chain(window_id)
-                    .map(|id| change_mode::<()>(id.unwrap(), Mode::Fullscreen))
-                    .discard(),
+                Task::batch([
+                    fetch_images.chain(fullscreen()).discard(),
+                    Task::run(server_messages, Message::Server),
+                ]),
             )
         })
 }
+
+fn main() -> iced::Result {
+    match std::env::args().nth(1).as_deref() {
+        Some("kitchen") => kitchen::run(),
+        Some("board") => board::run(),
+        _ => run_kiosk(),
+    }
+}
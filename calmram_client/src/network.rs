@@ -0,0 +1,75 @@
+use std::{fmt::Debug, marker::PhantomData, net::TcpStream, thread};
+
+use calmram_lib::protocol;
+use iced::futures::{channel::mpsc, Stream};
+use serde::{de::DeserializeOwned, Serialize};
+use websocket::{sync::Writer, ClientBuilder, Message, OwnedMessage};
+
+use crate::SERVER_URL;
+
+/// Our end of one of the server's WebSockets, sending messages of type `M`.
+pub struct Server<M> {
+    writer: Writer<TcpStream>,
+    message: PhantomData<M>,
+}
+
+impl<M: Serialize> Server<M> {
+    /// Connects to the server's WebSocket at `path`.
+    ///
+    /// Everything the server sends arrives on the returned stream, which is
+    /// read on a background thread so the UI never blocks on it.
+    pub fn connect<R>(path: &str) -> (Server<M>, impl Stream<Item = R> + Unpin)
+    where
+        R: DeserializeOwned + Debug + Send + 'static,
+    {
+        let client = ClientBuilder::new(&format!("ws://{SERVER_URL}/{path}"))
+            .unwrap()
+            .connect_insecure()
+            .unwrap();
+
+        let (mut reader, writer) = client.split().unwrap();
+        let (sender, receiver) = mpsc::unbounded();
+        thread::spawn(move || {
+            for message in reader.incoming_messages() {
+                match message {
+                    Ok(message) => {
+                        if let Some(message) = decode(message) {
+                            if sender.unbounded_send(message).is_err() {
+                                break;
+                            }
+                        }
+                    }
+                    Err(err) => {
+                        println!("Lost connection to server: {err}");
+                        break;
+                    }
+                }
+            }
+        });
+
+        let server = Server {
+            writer,
+            message: PhantomData,
+        };
+        (server, receiver)
+    }
+
+    pub fn send(&mut self, message: &M) {
+        self.writer
+            .send_message(&Message::text(protocol::encode(message)))
+            .unwrap();
+    }
+}
+
+fn decode<R: DeserializeOwned>(message: OwnedMessage) -> Option<R> {
+    match message {
+        OwnedMessage::Text(text) => match protocol::decode(&text) {
+            Ok(message) => Some(message),
+            Err(err) => {
+                println!("Received unexpected message {:?}: {}", text, err);
+                None
+            }
+        },
+        _ => None,
+    }
+}
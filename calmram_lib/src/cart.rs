@@ -0,0 +1,61 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{ItemId, Order};
+
+/// A configured item in a [`Cart`] and how many of it are wanted.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct CartItem {
+    pub order: Order,
+    pub quantity: u32,
+}
+
+impl CartItem {
+    /// The most of one configured item a cart can hold at once.
+    pub const MAX_QUANTITY: u32 = 99;
+}
+
+/// Everything a customer is ordering at once, bowls, sides and drinks alike.
+/// A cart is paid for and made as a single order with one order number.
+#[derive(Deserialize, Serialize, Clone, Debug, Default)]
+pub struct Cart {
+    pub items: Vec<CartItem>,
+}
+
+impl Cart {
+    pub fn new() -> Self {
+        Cart::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// How many of menu item `id` the cart holds, counting quantities.
+    pub fn count(&self, id: ItemId) -> u32 {
+        self.items
+            .iter()
+            .filter(|item| item.order.item == id)
+            .map(|item| item.quantity)
+            .sum()
+    }
+
+    pub fn add(&mut self, order: Order) {
+        self.items.push(CartItem { order, quantity: 1 });
+    }
+
+    /// Adds a single copy of the item at `index` right after it, ready to be
+    /// changed on its own.
+    pub fn duplicate(&mut self, index: usize) {
+        if let Some(item) = self.items.get(index) {
+            let order = item.order.clone();
+            self.items
+                .insert(index + 1, CartItem { order, quantity: 1 });
+        }
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if index < self.items.len() {
+            self.items.remove(index);
+        }
+    }
+}
@@ -0,0 +1,90 @@
+//! A small menu config shared by the unit tests.
+
+use crate::{Cart, CartItem, ChosenOption, Config, Order};
+
+pub const RAMEN: i32 = 1;
+pub const SHOYU: i32 = 2;
+pub const TONKOTSU: i32 = 3;
+pub const CHASHU: i32 = 4;
+pub const EGG: i32 = 5;
+pub const NORI: i32 = 6;
+pub const TEA: i32 = 10;
+
+/// Ramen at $10.00 with a choice of base, two toppings included and $0.50
+/// for each one past that (one with tonkotsu), and tea at $2.00 in a
+/// category of its own. No tax or tips unless a test sets them up.
+pub fn config() -> Config {
+    serde_json::from_str(
+        r#"{
+            "menu": {
+                "categories": [
+                    {
+                        "name": "Ramen",
+                        "image_url": "bowl.png",
+                        "items": [
+                            {
+                                "name": "Ramen",
+                                "price": 10.00,
+                                "image_url": "bowl.png",
+                                "id": 1,
+                                "modifier_groups": [
+                                    {
+                                        "name": "Base",
+                                        "min": 1,
+                                        "max": 1,
+                                        "options": [
+                                            {"name": "Shoyu", "id": 2, "default": true},
+                                            {"name": "Tonkotsu", "price": 1.00, "id": 3}
+                                        ]
+                                    },
+                                    {
+                                        "name": "Toppings",
+                                        "included": 2,
+                                        "extra_price": 0.50,
+                                        "allowances": {
+                                            "3": {"included": 1, "extra_price": 0.50}
+                                        },
+                                        "options": [
+                                            {"name": "Chashu", "price": 2.00, "id": 4, "max_quantity": 3},
+                                            {"name": "Egg", "id": 5, "default": true},
+                                            {"name": "Nori", "id": 6}
+                                        ]
+                                    }
+                                ]
+                            }
+                        ]
+                    },
+                    {
+                        "name": "Drinks",
+                        "image_url": "tea.png",
+                        "items": [
+                            {"name": "Tea", "price": 2.00, "image_url": "tea.png", "id": 10}
+                        ]
+                    }
+                ]
+            }
+        }"#,
+    )
+    .expect("the test config parses")
+}
+
+/// An order of `item` with one helping of each of `options`.
+pub fn order(item: i32, options: &[i32]) -> Order {
+    Order {
+        item,
+        options: options
+            .iter()
+            .map(|&id| ChosenOption { id, quantity: 1 })
+            .collect(),
+    }
+}
+
+/// A cart of `quantity` of each order.
+pub fn cart(items: Vec<(Order, u32)>) -> Cart {
+    Cart {
+        items: items
+            .into_iter()
+            .map(|(order, quantity)| CartItem { order, quantity })
+            .collect(),
+    }
+}
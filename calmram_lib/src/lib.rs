@@ -1,46 +1,334 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-pub use i32 as BaseId;
-pub use i32 as ToppingsId;
+mod cart;
+#[cfg(test)]
+mod fixtures;
+mod loyalty;
+mod money;
+mod pricing;
+mod promotion;
+pub mod protocol;
+mod schedule;
+mod status;
+mod tax;
+mod tender;
+mod tip;
+mod validation;
+
+pub use cart::{Cart, CartItem};
+pub use loyalty::{LoyaltyConfig, Reward};
+pub use money::Money;
+pub use pricing::{quote, ItemQuote, LineItem, LineKind, Quote};
+pub use promotion::{Discount, Promotion};
+pub use schedule::{is_scheduled, LocalTime, Schedule, Special, TimeOfDay, Weekday};
+pub use status::{InvalidTransition, OrderStatus};
+pub use tax::{Percent, Rounding, TaxConfig, TaxLine, TaxRate};
+pub use tender::{check_tenders, Tender};
+pub use tip::{Tip, TipBase, TipConfig};
+pub use validation::{ConfigError, OrderError};
+
+pub use i32 as ItemId;
+pub use i32 as OptionId;
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct Base {
+/// Something a customer can order, such as a bowl of ramen or a drink, along
+/// with the choices they make about it.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct MenuItem {
     pub name: String,
-    pub price: f32,
+    pub price: Money,
     pub image_url: String,
-    pub id: BaseId,
+    pub id: ItemId,
+    #[serde(default)]
+    pub sold_out: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock: Option<u32>,
+    #[serde(default)]
+    pub modifier_groups: Vec<ModifierGroup>,
+    /// When the item is offered, if not all day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
+    /// Prices that replace `price` at certain times. The first one scheduled
+    /// wins.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub specials: Vec<Special>,
+}
+
+/// How the kiosk shows the options of a [`ModifierGroup`].
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GroupStyle {
+    /// A button with a picture for each option.
+    #[default]
+    Pictures,
+    /// A row of the group's picture, filled up to the chosen option, for
+    /// options that are degrees of one thing like spiciness. Only single
+    /// choice groups can use it.
+    Scale,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct Toppings {
+/// A choice to make about a [`MenuItem`], like its broth or its toppings.
+///
+/// A group with a `max` of 1 is single choice: picking another option
+/// replaces the last one. Otherwise options are toggled on and off, between
+/// `min` and `max` of them.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModifierGroup {
     pub name: String,
-    pub price: Option<f32>,
-    pub image_url: String,
-    pub id: ToppingsId,
+    #[serde(default)]
+    pub min: u32,
+    /// No limit when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<u32>,
+    #[serde(default)]
+    pub style: GroupStyle,
+    /// Shown for each step of a [`GroupStyle::Scale`].
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    /// How many helpings from the group come with the item before each one
+    /// past that costs `extra_price` on top of its own price. All of them
+    /// when missing.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub included: Option<u32>,
+    #[serde(default)]
+    pub extra_price: Money,
+    /// Allowances that replace `included` and `extra_price` when an option
+    /// of another group is chosen, keyed by that option, such as fewer
+    /// toppings coming with a pricier base. The options all come from one
+    /// single choice group, so at most one of them applies.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub allowances: BTreeMap<OptionId, Allowance>,
+    pub options: Vec<ModifierOption>,
+}
+
+/// How many helpings from a [`ModifierGroup`] come with an item, and what
+/// each one past that costs.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Allowance {
+    pub included: u32,
+    #[serde(default)]
+    pub extra_price: Money,
+}
+
+impl Allowance {
+    /// How many of `count` helpings go past the allowance.
+    pub fn extras(self, count: u32) -> u32 {
+        count.saturating_sub(self.included)
+    }
+}
+
+/// One option of a [`ModifierGroup`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ModifierOption {
+    pub name: String,
+    /// Added to the price of the item.
+    #[serde(default)]
+    pub price: Money,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_url: Option<String>,
+    pub id: OptionId,
+    /// Whether a new order starts with this option chosen.
+    #[serde(default)]
+    pub default: bool,
+    /// How many helpings of the option one item can have, each charged at
+    /// `price`.
+    #[serde(default = "one")]
+    pub max_quantity: u32,
+    #[serde(default)]
+    pub sold_out: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stock: Option<u32>,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl ModifierGroup {
+    /// The allowance of this group for `order`: the one for the option
+    /// chosen from `allowances`, if any, or else `included` helpings at
+    /// `extra_price`. Every helping is included when there is neither.
+    /// Should an order have more than one of the options, the one with the
+    /// lowest id wins.
+    pub fn allowance(&self, order: &Order) -> Option<Allowance> {
+        self.allowances
+            .iter()
+            .find(|(id, _)| order.is_chosen(**id))
+            .map(|(_, allowance)| *allowance)
+            .or_else(|| {
+                self.included.map(|included| Allowance {
+                    included,
+                    extra_price: self.extra_price,
+                })
+            })
+    }
+
+    pub fn is_single_choice(&self) -> bool {
+        self.max == Some(1)
+    }
+
+    pub fn option(&self, id: OptionId) -> Option<&ModifierOption> {
+        self.options.iter().find(|option| option.id == id)
+    }
+}
+
+impl MenuItem {
+    /// The group offering option `id`, if this item has one.
+    pub fn group_of(&self, id: OptionId) -> Option<&ModifierGroup> {
+        self.modifier_groups
+            .iter()
+            .find(|group| group.option(id).is_some())
+    }
+
+    pub fn option(&self, id: OptionId) -> Option<&ModifierOption> {
+        self.modifier_groups
+            .iter()
+            .find_map(|group| group.option(id))
+    }
+
+    /// Every option of every group.
+    pub fn options(&self) -> impl Iterator<Item = &ModifierOption> {
+        self.modifier_groups.iter().flat_map(|group| &group.options)
+    }
 }
 
-#[derive(Deserialize, Serialize, Clone)]
-pub struct SpiceLevel {
+/// A section of the menu, like ramen, sides or drinks.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Category {
     pub name: String,
-    pub level: i32,
+    pub image_url: String,
+    pub items: Vec<MenuItem>,
+    /// When the category is offered, like a lunch menu, if not all day.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub schedules: Vec<Schedule>,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Menu {
-    pub bases: Vec<Base>,
-    pub toppings: Vec<Toppings>,
-    pub spice_levels: Vec<SpiceLevel>,
+    pub categories: Vec<Category>,
+}
+
+impl Menu {
+    /// The menu as it is offered at `now`: categories and items that are not
+    /// scheduled are left out, as are categories left empty, and specials
+    /// replace the usual prices.
+    pub fn at(&self, now: LocalTime) -> Menu {
+        let categories = self
+            .categories
+            .iter()
+            .filter(|category| is_scheduled(&category.schedules, now))
+            .map(|category| Category {
+                items: category
+                    .items
+                    .iter()
+                    .filter(|item| is_scheduled(&item.schedules, now))
+                    .map(|item| {
+                        let special = item.specials.iter().find(|special| {
+                            special
+                                .schedules
+                                .iter()
+                                .any(|schedule| schedule.contains(now))
+                        });
+                        MenuItem {
+                            price: special.map_or(item.price, |special| special.price),
+                            ..item.clone()
+                        }
+                    })
+                    .collect(),
+                ..category.clone()
+            })
+            .filter(|category| !category.items.is_empty());
+        Menu {
+            categories: categories.collect(),
+        }
+    }
+
+    /// Every item of every category.
+    pub fn items(&self) -> impl Iterator<Item = &MenuItem> {
+        self.categories.iter().flat_map(|category| &category.items)
+    }
+
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut MenuItem> {
+        self.categories
+            .iter_mut()
+            .flat_map(|category| &mut category.items)
+    }
+
+    pub fn item(&self, id: ItemId) -> Option<&MenuItem> {
+        self.items().find(|item| item.id == id)
+    }
+
+    /// The category item `id` is listed in.
+    pub fn category_of(&self, id: ItemId) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|category| category.items.iter().any(|item| item.id == id))
+    }
+
+    /// Whether the item or option `id` can be ordered. Ids that are not on
+    /// the menu count as available; [`Order::validate`] reports those.
+    pub fn is_available(&self, id: i32) -> bool {
+        let sold_out = self.item(id).map(|item| item.sold_out);
+        let sold_out = sold_out.or_else(|| {
+            self.items()
+                .find_map(|item| item.option(id))
+                .map(|option| option.sold_out)
+        });
+        !sold_out.unwrap_or(false)
+    }
+
+    /// A fresh order of `item` with its default options, less any that are
+    /// sold out.
+    pub fn new_order(&self, item: ItemId) -> Order {
+        let options = self.item(item).into_iter().flat_map(|item| item.options());
+        let mut order = Order {
+            item,
+            options: options
+                .filter(|option| option.default)
+                .map(|option| ChosenOption {
+                    id: option.id,
+                    quantity: 1,
+                })
+                .collect(),
+        };
+        order.reconcile(self);
+        order
+    }
 }
 
+/// One configured [`MenuItem`]: the item and every option chosen for it.
 #[derive(Deserialize, Serialize, Clone, Debug)]
 pub struct Order {
-    pub base: BaseId,
-    pub toppings: Vec<ToppingsId>,
-    pub spice_level: i32,
+    pub item: ItemId,
+    pub options: Vec<ChosenOption>,
 }
 
-#[derive(Deserialize, Serialize, Clone)]
+/// An option chosen for an [`Order`], and how many helpings of it.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ChosenOption {
+    pub id: OptionId,
+    #[serde(default = "one")]
+    pub quantity: u32,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
 pub struct Config {
     pub menu: Menu,
-    pub default_order: Order,
+    #[serde(default)]
+    pub tax: TaxConfig,
+    #[serde(default)]
+    pub tip: TipConfig,
+    #[serde(default)]
+    pub loyalty: LoyaltyConfig,
+}
+
+impl Config {
+    /// The config with the menu offered at `now`; see [`Menu::at`].
+    pub fn at(&self, now: LocalTime) -> Config {
+        Config {
+            menu: self.menu.at(now),
+            ..self.clone()
+        }
+    }
 }
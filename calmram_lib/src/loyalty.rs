@@ -0,0 +1,59 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Discount, Promotion, Quote};
+
+/// The `loyalty` section of the menu config. Customers are not asked for
+/// their phone number without one.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct LoyaltyConfig {
+    /// Points earned for each whole dollar an order costs before tax and tip.
+    #[serde(default)]
+    pub points_per_dollar: u32,
+    #[serde(default)]
+    pub rewards: Vec<Reward>,
+}
+
+impl LoyaltyConfig {
+    /// Whether customers can collect points at all.
+    pub fn enabled(&self) -> bool {
+        self.points_per_dollar > 0 || !self.rewards.is_empty()
+    }
+
+    pub fn reward(&self, id: i32) -> Option<&Reward> {
+        self.rewards.iter().find(|reward| reward.id == id)
+    }
+
+    /// The points an order priced at `quote` earns.
+    pub fn points(&self, quote: &Quote) -> u32 {
+        let dollars = quote.before_tax().cents().max(0) / 100;
+        u32::try_from(dollars)
+            .unwrap_or(u32::MAX)
+            .saturating_mul(self.points_per_dollar)
+    }
+}
+
+/// Something a customer can spend their points on, such as a free topping
+/// or a free bowl.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Reward {
+    pub id: i32,
+    /// Shown on the kiosk and as the discount line of a [`Quote`].
+    pub name: String,
+    pub points: u32,
+    pub discount: Discount,
+}
+
+impl Reward {
+    /// The reward as a [`Promotion`] to price an order with. Rewards take
+    /// the place of a promo code, so an order can have one or the other.
+    pub fn promotion(&self) -> Promotion {
+        Promotion {
+            id: self.id,
+            code: String::new(),
+            name: self.name.clone(),
+            discount: self.discount.clone(),
+        }
+    }
+}
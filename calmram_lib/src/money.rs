@@ -0,0 +1,205 @@
+use std::{
+    convert::TryFrom,
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// An exact amount of money, stored as a whole number of cents.
+///
+/// Menu files write prices as plain decimal numbers (`10.00`), which are
+/// rounded to the nearest cent once on load. All arithmetic after that is
+/// integer arithmetic, so every client and the server agree to the cent.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub const fn from_cents(cents: i64) -> Self {
+        Money(cents)
+    }
+
+    pub const fn cents(self) -> i64 {
+        self.0
+    }
+
+    pub const fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, rhs: Money) -> Money {
+        Money(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, rhs: Money) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, rhs: Money) -> Money {
+        Money(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Money {
+    fn sub_assign(&mut self, rhs: Money) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Money {
+    type Output = Money;
+
+    fn neg(self) -> Money {
+        Money(-self.0)
+    }
+}
+
+impl Mul<u32> for Money {
+    type Output = Money;
+
+    fn mul(self, rhs: u32) -> Money {
+        Money(self.0 * i64::from(rhs))
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let cents = self.0.unsigned_abs();
+        write!(f, "{sign}${}.{:02}", cents / 100, cents % 100)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0 as f64 / 100.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct MoneyVisitor;
+
+        impl de::Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an amount of money")
+            }
+
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+                v.checked_mul(100)
+                    .map(Money)
+                    .ok_or_else(|| E::custom("amount of money out of range"))
+            }
+
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+                i64::try_from(v)
+                    .map_err(|_| E::custom("amount of money out of range"))
+                    .and_then(|v| self.visit_i64(v))
+            }
+
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+                let cents = (v * 100.0).round();
+                if cents.is_finite() && cents.abs() < i64::MAX as f64 {
+                    Ok(Money(cents as i64))
+                } else {
+                    Err(E::custom("amount of money out of range"))
+                }
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(json: &str) -> Result<Money, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    #[test]
+    fn displays_dollars_and_cents() {
+        assert_eq!(Money::from_cents(1050).to_string(), "$10.50");
+        assert_eq!(Money::from_cents(5).to_string(), "$0.05");
+        assert_eq!(Money::from_cents(-250).to_string(), "-$2.50");
+        assert_eq!(Money::ZERO.to_string(), "$0.00");
+    }
+
+    #[test]
+    fn serializes_as_decimal_dollars() {
+        assert_eq!(
+            serde_json::to_string(&Money::from_cents(1050)).unwrap(),
+            "10.5"
+        );
+        assert_eq!(
+            serde_json::to_string(&Money::from_cents(-199)).unwrap(),
+            "-1.99"
+        );
+        assert_eq!(serde_json::to_string(&Money::ZERO).unwrap(), "0.0");
+    }
+
+    #[test]
+    fn deserializes_to_the_nearest_cent() {
+        assert_eq!(parse("10.25").unwrap(), Money::from_cents(1025));
+        assert_eq!(parse("0.1").unwrap(), Money::from_cents(10));
+        assert_eq!(parse("8.875").unwrap(), Money::from_cents(888));
+        assert_eq!(parse("-2.5").unwrap(), Money::from_cents(-250));
+        assert_eq!(parse("3").unwrap(), Money::from_cents(300));
+    }
+
+    #[test]
+    fn survives_a_round_trip() {
+        for cents in [0, 1, 10, 99, 1050, 123_456_789, -1, -1050] {
+            let money = Money::from_cents(cents);
+            let json = serde_json::to_string(&money).unwrap();
+            assert_eq!(parse(&json).unwrap(), money, "{json}");
+        }
+    }
+
+    #[test]
+    fn rejects_amounts_out_of_range() {
+        assert!(parse(&i64::MAX.to_string()).is_err());
+        assert!(parse(&u64::MAX.to_string()).is_err());
+        assert!(parse("1e300").is_err());
+        assert!(parse("\"10.00\"").is_err());
+    }
+
+    #[test]
+    fn adds_up() {
+        let amounts = [Money::from_cents(250), Money::from_cents(-100)];
+        assert_eq!(
+            amounts.iter().copied().sum::<Money>(),
+            Money::from_cents(150)
+        );
+        assert_eq!(Money::from_cents(250) * 3, Money::from_cents(750));
+        assert_eq!(-Money::from_cents(250), Money::from_cents(-250));
+
+        let mut money = Money::from_cents(100);
+        money += Money::from_cents(50);
+        money -= Money::from_cents(200);
+        assert_eq!(money, Money::from_cents(-50));
+    }
+}
@@ -0,0 +1,362 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Cart, Config, Menu, Money, Order, OrderError, Promotion, TaxLine, Tip, TipBase};
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LineKind {
+    Item,
+    Modifier,
+    /// Helpings from a modifier group past what comes with the item.
+    Extra,
+    /// Taken off the order by a [`Promotion`].
+    Discount,
+}
+
+impl LineKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineKind::Item => "item",
+            LineKind::Modifier => "modifier",
+            LineKind::Extra => "extra",
+            LineKind::Discount => "discount",
+        }
+    }
+}
+
+/// One priced line of a [`Quote`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct LineItem {
+    pub kind: LineKind,
+    pub id: i32,
+    pub name: String,
+    /// The modifier group a [`LineKind::Modifier`] or [`LineKind::Extra`]
+    /// line belongs to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// How many helpings the line is for; `price` is for a single one.
+    #[serde(default = "one")]
+    pub quantity: u32,
+    pub price: Money,
+}
+
+fn one() -> u32 {
+    1
+}
+
+impl LineItem {
+    /// The price of every helping on this line together.
+    pub fn total(&self) -> Money {
+        self.price * self.quantity
+    }
+}
+
+/// The priced lines of one item of a [`Quote`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct ItemQuote {
+    pub lines: Vec<LineItem>,
+    pub quantity: u32,
+    /// The price of a single one of the item.
+    pub unit_price: Money,
+    pub total: Money,
+    /// How much of `total` a promotion takes off.
+    #[serde(default)]
+    pub discount: Money,
+}
+
+/// An itemized price for a cart.
+///
+/// This is the only place prices are added up. The kiosk shows a quote on
+/// its summary screen and the server stores the same quote, so the two can
+/// never disagree.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+pub struct Quote {
+    pub items: Vec<ItemQuote>,
+    pub subtotal: Money,
+    /// What the promotion applied to the order takes off, as a negative
+    /// price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discount: Option<LineItem>,
+    pub taxes: Vec<TaxLine>,
+    /// Every tax in `taxes` together.
+    pub tax: Money,
+    /// Whether `tax` is already part of `subtotal` rather than added to it.
+    pub tax_inclusive: bool,
+    /// What the customer chose to tip, which is part of `total`.
+    #[serde(default)]
+    pub tip: Money,
+    pub total: Money,
+}
+
+impl Quote {
+    /// What the order costs after any discount, without tax or tip.
+    pub fn before_tax(&self) -> Money {
+        let discounted = self.subtotal
+            + self
+                .discount
+                .as_ref()
+                .map_or(Money::ZERO, |line| line.price);
+        if self.tax_inclusive {
+            discounted - self.tax
+        } else {
+            discounted
+        }
+    }
+}
+
+/// Prices `cart` against the menu of `config`, takes off what `promotion`
+/// gives, taxes the rest and adds `tip`, rejecting carts that fail
+/// [`Cart::validate`] and tips the config does not offer.
+pub fn quote(
+    config: &Config,
+    cart: &Cart,
+    promotion: Option<&Promotion>,
+    tip: Option<Tip>,
+) -> Result<Quote, OrderError> {
+    let menu = &config.menu;
+    cart.validate(menu)?;
+
+    let mut items = cart
+        .items
+        .iter()
+        .map(|item| quote_item(menu, &item.order, item.quantity))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let discount = promotion.map(|promotion| {
+        let discounts = promotion.discount.apply(cart, &items);
+        for (item, discount) in items.iter_mut().zip(discounts) {
+            item.discount = discount;
+        }
+        LineItem {
+            kind: LineKind::Discount,
+            id: promotion.id,
+            name: promotion.name.clone(),
+            group: None,
+            quantity: 1,
+            price: -items.iter().map(|item| item.discount).sum::<Money>(),
+        }
+    });
+
+    let prices: Vec<(&str, Money)> = cart
+        .items
+        .iter()
+        .zip(&items)
+        .map(|(item, quote)| {
+            let category = menu.category_of(item.order.item);
+            (
+                category.map_or("", |category| category.name.as_str()),
+                quote.total - quote.discount,
+            )
+        })
+        .collect();
+    let taxes = config.tax.apply(&prices);
+
+    let subtotal = items.iter().map(|item| item.total).sum();
+    let discounted = subtotal + discount.as_ref().map_or(Money::ZERO, |line| line.price);
+    let tax = taxes.iter().map(|line| line.amount).sum();
+    let tax_inclusive = config.tax.inclusive;
+    let (before_tax, after_tax) = if tax_inclusive {
+        (discounted - tax, discounted)
+    } else {
+        (discounted, discounted + tax)
+    };
+
+    let tip = match tip {
+        Some(tip) if !config.tip.offers(tip) => return Err(OrderError::TipNotOffered),
+        Some(tip) => config.tip.amount(
+            tip,
+            match config.tip.base {
+                TipBase::BeforeTax => before_tax,
+                TipBase::AfterTax => after_tax,
+            },
+        ),
+        None => Money::ZERO,
+    };
+
+    Ok(Quote {
+        items,
+        subtotal,
+        discount,
+        taxes,
+        tax,
+        tax_inclusive,
+        tip,
+        total: after_tax + tip,
+    })
+}
+
+fn quote_item(menu: &Menu, order: &Order, quantity: u32) -> Result<ItemQuote, OrderError> {
+    let item = menu
+        .item(order.item)
+        .ok_or(OrderError::UnknownItem(order.item))?;
+    let mut lines = vec![LineItem {
+        kind: LineKind::Item,
+        id: item.id,
+        name: item.name.clone(),
+        group: None,
+        quantity: 1,
+        price: item.price,
+    }];
+
+    for group in &item.modifier_groups {
+        for chosen in order.chosen(group) {
+            let option = group
+                .option(chosen.id)
+                .ok_or(OrderError::UnknownOption(chosen.id))?;
+            lines.push(LineItem {
+                kind: LineKind::Modifier,
+                id: option.id,
+                name: option.name.clone(),
+                group: Some(group.name.clone()),
+                quantity: chosen.quantity,
+                price: option.price,
+            });
+        }
+
+        if let Some(allowance) = group.allowance(order) {
+            let extras = allowance.extras(order.chosen_count(group));
+            if extras > 0 && !allowance.extra_price.is_zero() {
+                lines.push(LineItem {
+                    kind: LineKind::Extra,
+                    id: item.id,
+                    name: format!("Extra {}", group.name.to_lowercase()),
+                    group: Some(group.name.clone()),
+                    quantity: extras,
+                    price: allowance.extra_price,
+                });
+            }
+        }
+    }
+
+    let unit_price: Money = lines.iter().map(LineItem::total).sum();
+    Ok(ItemQuote {
+        lines,
+        quantity,
+        unit_price,
+        total: unit_price * quantity,
+        discount: Money::ZERO,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{cart, config, order, CHASHU, EGG, NORI, RAMEN, SHOYU, TEA, TONKOTSU},
+        ChosenOption, Discount,
+    };
+
+    fn cents(cents: i64) -> Money {
+        Money::from_cents(cents)
+    }
+
+    #[test]
+    fn adds_options_to_the_item_price() {
+        let config = config();
+        let quote = quote(
+            &config,
+            &cart(vec![(order(RAMEN, &[TONKOTSU]), 1), (order(TEA, &[]), 1)]),
+            None,
+            None,
+        )
+        .unwrap();
+
+        let ramen = &quote.items[0];
+        let kinds: Vec<_> = ramen.lines.iter().map(|line| line.kind).collect();
+        assert_eq!(kinds, [LineKind::Item, LineKind::Modifier]);
+        assert_eq!(ramen.unit_price, cents(1100));
+        assert_eq!(quote.items[1].unit_price, cents(200));
+        assert_eq!(quote.subtotal, cents(1300));
+        assert_eq!(quote.total, cents(1300));
+        assert_eq!(quote.discount, None);
+    }
+
+    #[test]
+    fn multiplies_by_quantity() {
+        let config = config();
+        let mut chashu = order(RAMEN, &[SHOYU]);
+        chashu.options.push(ChosenOption {
+            id: CHASHU,
+            quantity: 2,
+        });
+        let quote = quote(&config, &cart(vec![(chashu, 3)]), None, None).unwrap();
+
+        let line = &quote.items[0].lines[2];
+        assert_eq!((line.quantity, line.total()), (2, cents(400)));
+        assert_eq!(quote.items[0].unit_price, cents(1400));
+        assert_eq!(quote.items[0].total, cents(4200));
+        assert_eq!(quote.total, cents(4200));
+    }
+
+    #[test]
+    fn charges_helpings_past_the_allowance() {
+        let config = config();
+        let within = order(RAMEN, &[SHOYU, EGG, NORI]);
+        let past = order(RAMEN, &[SHOYU, EGG, NORI, CHASHU]);
+        let quote = quote(&config, &cart(vec![(within, 1), (past, 1)]), None, None).unwrap();
+
+        assert!(quote.items[0]
+            .lines
+            .iter()
+            .all(|line| line.kind != LineKind::Extra));
+        let extra = quote.items[1].lines.last().unwrap();
+        assert_eq!(extra.kind, LineKind::Extra);
+        assert_eq!(extra.name, "Extra toppings");
+        assert_eq!((extra.quantity, extra.price), (1, cents(50)));
+        assert_eq!(quote.items[1].unit_price, cents(1250));
+    }
+
+    #[test]
+    fn uses_the_allowance_of_the_chosen_base() {
+        let config = config();
+        let shoyu = order(RAMEN, &[SHOYU, EGG, NORI]);
+        let tonkotsu = order(RAMEN, &[TONKOTSU, EGG, NORI]);
+        let quote = quote(&config, &cart(vec![(shoyu, 1), (tonkotsu, 1)]), None, None).unwrap();
+
+        assert_eq!(quote.items[0].unit_price, cents(1000));
+        let extra = quote.items[1].lines.last().unwrap();
+        assert_eq!((extra.kind, extra.quantity), (LineKind::Extra, 1));
+        assert_eq!(quote.items[1].unit_price, cents(1150));
+    }
+
+    #[test]
+    fn takes_the_discount_off_the_total() {
+        let config = config();
+        let promotion = Promotion {
+            id: 7,
+            code: "TEA".to_string(),
+            name: "Free tea".to_string(),
+            discount: Discount::FreeItem { item: TEA },
+        };
+        let quote = quote(
+            &config,
+            &cart(vec![(order(RAMEN, &[SHOYU]), 1), (order(TEA, &[]), 2)]),
+            Some(&promotion),
+            None,
+        )
+        .unwrap();
+
+        let discount = quote.discount.as_ref().unwrap();
+        assert_eq!((discount.kind, discount.id), (LineKind::Discount, 7));
+        assert_eq!(discount.price, cents(-200));
+        assert_eq!(quote.subtotal, cents(1400));
+        assert_eq!(quote.before_tax(), cents(1200));
+        assert_eq!(quote.total, cents(1200));
+    }
+
+    #[test]
+    fn rejects_carts_that_cannot_be_made() {
+        let config = config();
+        assert_eq!(
+            quote(&config, &cart(Vec::new()), None, None),
+            Err(OrderError::EmptyCart)
+        );
+        assert_eq!(
+            quote(&config, &cart(vec![(order(RAMEN, &[]), 1)]), None, None),
+            Err(OrderError::TooFewOptions {
+                group: "Base".to_string(),
+                min: 1
+            })
+        );
+    }
+}
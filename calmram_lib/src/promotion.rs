@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Cart, ItemId, ItemQuote, LineKind, Money, OptionId, Percent, Rounding};
+
+/// What a [`Promotion`] takes off an order.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discount {
+    /// A percentage off every item.
+    PercentOff { percent: Percent },
+    /// A fixed amount off the order, never more than it costs.
+    AmountOff { amount: Money },
+    /// Every second one of `item` ordered is free, cheapest first.
+    BuyOneGetOne { item: ItemId },
+    /// One of `item` is free, the cheapest one ordered, though not the
+    /// options chosen for it.
+    FreeItem { item: ItemId },
+    /// One helping of `option` is free on every item it is chosen for.
+    FreeOption { option: OptionId },
+    /// One helping of `option` is free, on the cheapest item it is chosen
+    /// for.
+    OneFreeOption { option: OptionId },
+}
+
+/// A promo code the server has accepted.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Promotion {
+    pub id: i32,
+    pub code: String,
+    /// Shown as the discount line of a [`crate::Quote`].
+    pub name: String,
+    pub discount: Discount,
+}
+
+impl Discount {
+    /// How much comes off each of `items`, the quoted items of `cart`.
+    pub(crate) fn apply(&self, cart: &Cart, items: &[ItemQuote]) -> Vec<Money> {
+        match self {
+            Discount::PercentOff { percent } => items
+                .iter()
+                .map(|item| {
+                    // Never more than the item costs.
+                    let percent = percent.thousandths().min(100_000);
+                    let off = i128::from(item.total.cents()) * i128::from(percent);
+                    Money::from_cents(Rounding::HalfUp.divide(off, 100_000) as i64)
+                })
+                .collect(),
+            Discount::AmountOff { amount } => {
+                let mut left = (*amount).max(Money::ZERO);
+                items
+                    .iter()
+                    .map(|item| {
+                        let off = left.min(item.total);
+                        left -= off;
+                        off
+                    })
+                    .collect()
+            }
+            Discount::BuyOneGetOne { item: id } => {
+                let mut offers: Vec<usize> = (0..items.len())
+                    .filter(|index| cart.items[*index].order.item == *id)
+                    .collect();
+                let ordered: u32 = offers.iter().map(|index| items[*index].quantity).sum();
+                offers.sort_by_key(|index| items[*index].unit_price);
+
+                let mut free = ordered / 2;
+                let mut discounts = vec![Money::ZERO; items.len()];
+                for index in offers {
+                    let quantity = free.min(items[index].quantity);
+                    discounts[index] = items[index].unit_price * quantity;
+                    free -= quantity;
+                }
+                discounts
+            }
+            Discount::FreeItem { item: id } => {
+                let mut discounts = vec![Money::ZERO; items.len()];
+                let free = (0..items.len())
+                    .filter(|index| cart.items[*index].order.item == *id)
+                    .min_by_key(|index| items[*index].unit_price);
+                if let Some(index) = free {
+                    let line = items[index]
+                        .lines
+                        .iter()
+                        .find(|line| line.kind == LineKind::Item);
+                    discounts[index] = line.map_or(Money::ZERO, |line| line.price);
+                }
+                discounts
+            }
+            Discount::FreeOption { option } => items
+                .iter()
+                .map(|item| {
+                    let line = item
+                        .lines
+                        .iter()
+                        .find(|line| line.kind == LineKind::Modifier && line.id == *option);
+                    line.map_or(Money::ZERO, |line| line.price * item.quantity)
+                })
+                .collect(),
+            Discount::OneFreeOption { option } => {
+                let option_line = |item: &ItemQuote| {
+                    item.lines
+                        .iter()
+                        .find(|line| line.kind == LineKind::Modifier && line.id == *option)
+                        .map(|line| line.price)
+                };
+                let mut discounts = vec![Money::ZERO; items.len()];
+                let free = (0..items.len())
+                    .filter(|index| option_line(&items[*index]).is_some())
+                    .min_by_key(|index| items[*index].unit_price);
+                if let Some(index) = free {
+                    discounts[index] = option_line(&items[index]).unwrap_or_default();
+                }
+                discounts
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{cart, config, order, CHASHU, EGG, RAMEN, SHOYU, TEA, TONKOTSU},
+        quote, ChosenOption, Order,
+    };
+
+    /// How much `discount` takes off each of `items`, in cents.
+    fn discounts(discount: Discount, items: Vec<(Order, u32)>) -> Vec<i64> {
+        let promotion = Promotion {
+            id: 1,
+            code: "TEST".to_string(),
+            name: "Test".to_string(),
+            discount,
+        };
+        let quote = quote(&config(), &cart(items), Some(&promotion), None).unwrap();
+        quote
+            .items
+            .iter()
+            .map(|item| item.discount.cents())
+            .collect()
+    }
+
+    /// Ramen at $10.00.
+    fn shoyu() -> Order {
+        order(RAMEN, &[SHOYU, EGG])
+    }
+
+    /// Ramen at $11.00.
+    fn tonkotsu() -> Order {
+        order(RAMEN, &[TONKOTSU, EGG])
+    }
+
+    /// Ramen at $12.00, with chashu.
+    fn chashu() -> Order {
+        order(RAMEN, &[SHOYU, EGG, CHASHU])
+    }
+
+    #[test]
+    fn percent_off_every_item() {
+        let percent = Percent::from_thousandths(12_500);
+        let off = discounts(
+            Discount::PercentOff { percent },
+            vec![(shoyu(), 2), (order(TEA, &[]), 1)],
+        );
+        // 12.5% of $2.00 is exactly 25 cents; of $20.00, $2.50.
+        assert_eq!(off, [250, 25]);
+
+        let percent = Percent::from_thousandths(3_333);
+        let off = discounts(Discount::PercentOff { percent }, vec![(shoyu(), 1)]);
+        // 33.33 cents rounds half up to 33.
+        assert_eq!(off, [33]);
+    }
+
+    #[test]
+    fn percent_off_never_takes_more_than_the_item() {
+        let percent = Percent::from_thousandths(150_000);
+        let off = discounts(Discount::PercentOff { percent }, vec![(shoyu(), 1)]);
+        assert_eq!(off, [1000]);
+    }
+
+    #[test]
+    fn amount_off_stops_at_the_order_total() {
+        let amount = Money::from_cents(1100);
+        let off = discounts(
+            Discount::AmountOff { amount },
+            vec![(shoyu(), 1), (order(TEA, &[]), 1)],
+        );
+        assert_eq!(off, [1000, 100]);
+
+        let amount = Money::from_cents(5000);
+        let off = discounts(
+            Discount::AmountOff { amount },
+            vec![(shoyu(), 1), (order(TEA, &[]), 1)],
+        );
+        assert_eq!(off, [1000, 200]);
+    }
+
+    #[test]
+    fn amount_off_below_zero_takes_nothing() {
+        let amount = Money::from_cents(-500);
+        let off = discounts(Discount::AmountOff { amount }, vec![(shoyu(), 1)]);
+        assert_eq!(off, [0]);
+    }
+
+    #[test]
+    fn buy_one_get_one_frees_the_cheapest() {
+        let item = RAMEN;
+        let off = discounts(
+            Discount::BuyOneGetOne { item },
+            vec![(chashu(), 1), (shoyu(), 1), (tonkotsu(), 1)],
+        );
+        // Three bowls make one free, and the cheapest is the shoyu.
+        assert_eq!(off, [0, 1000, 0]);
+
+        let off = discounts(
+            Discount::BuyOneGetOne { item },
+            vec![(chashu(), 2), (tonkotsu(), 1), (shoyu(), 1)],
+        );
+        assert_eq!(off, [0, 1100, 1000]);
+
+        let off = discounts(
+            Discount::BuyOneGetOne { item },
+            vec![(chashu(), 3), (shoyu(), 1)],
+        );
+        assert_eq!(off, [1200, 1000]);
+    }
+
+    #[test]
+    fn buy_one_get_one_needs_two() {
+        let off = discounts(
+            Discount::BuyOneGetOne { item: RAMEN },
+            vec![(shoyu(), 1), (order(TEA, &[]), 3)],
+        );
+        assert_eq!(off, [0, 0]);
+    }
+
+    #[test]
+    fn free_item_leaves_the_options_charged() {
+        let off = discounts(
+            Discount::FreeItem { item: RAMEN },
+            vec![(tonkotsu(), 2), (chashu(), 1)],
+        );
+        // The cheapest bowl's $10.00 base price, not its $1.00 tonkotsu.
+        assert_eq!(off, [1000, 0]);
+
+        let off = discounts(Discount::FreeItem { item: TEA }, vec![(shoyu(), 1)]);
+        assert_eq!(off, [0]);
+    }
+
+    #[test]
+    fn free_option_on_every_item() {
+        let mut double = order(RAMEN, &[SHOYU]);
+        double.options.push(ChosenOption {
+            id: CHASHU,
+            quantity: 2,
+        });
+        let off = discounts(
+            Discount::FreeOption { option: CHASHU },
+            vec![(double, 2), (chashu(), 1), (shoyu(), 1)],
+        );
+        // One helping per bowl, however many helpings it has.
+        assert_eq!(off, [400, 200, 0]);
+    }
+
+    #[test]
+    fn one_free_option_on_the_cheapest_item() {
+        let mut pricier = order(RAMEN, &[TONKOTSU, EGG]);
+        pricier.options.push(ChosenOption {
+            id: CHASHU,
+            quantity: 1,
+        });
+        let off = discounts(
+            Discount::OneFreeOption { option: CHASHU },
+            vec![(pricier, 1), (chashu(), 2)],
+        );
+        assert_eq!(off, [0, 200]);
+    }
+}
@@ -0,0 +1,247 @@
+//! Messages exchanged with the server over its WebSockets: kiosks use
+//! `/kiosk`, kitchen screens use `/kitchen` and pickup boards use `/board`.
+//!
+//! Every message travels as JSON text wrapped in an envelope carrying
+//! [`PROTOCOL_VERSION`], so a kiosk and server built from different releases
+//! refuse each other's messages instead of misreading them.
+
+use std::{error::Error, fmt};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{Cart, Config, Menu, Money, Order, OrderStatus, Promotion, Tender, Tip};
+
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Sent by a kiosk to the server.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum ClientMessage {
+    /// Asks for the current [`Config`]; answered with
+    /// [`ServerMessage::MenuUpdated`].
+    GetConfig,
+    /// Asks whether a promo code can be used; answered with
+    /// [`ServerMessage::PromoAccepted`] or [`ServerMessage::PromoRejected`].
+    CheckPromo(String),
+    /// Asks what is left on a gift card; answered with
+    /// [`ServerMessage::GiftCardBalance`] or
+    /// [`ServerMessage::GiftCardRejected`].
+    CheckGiftCard(String),
+    /// Asks how many loyalty points the customer with a phone number has;
+    /// answered with [`ServerMessage::LoyaltyBalance`] or
+    /// [`ServerMessage::LoyaltyRejected`].
+    CheckLoyalty(String),
+    /// Has the customer pay at the terminal, then places the order. The
+    /// promo code, if any, is checked again and used up when the order is
+    /// accepted. The tip is paid along with the order.
+    SubmitOrder {
+        cart: Cart,
+        promo_code: Option<String>,
+        tip: Option<Tip>,
+        /// How the order is split between payments, which must add up to
+        /// its total. Empty to pay it all by card.
+        #[serde(default)]
+        tenders: Vec<Tender>,
+        /// The phone number of the customer collecting points for the order.
+        #[serde(default)]
+        phone: Option<String>,
+        /// The [`crate::Reward`] the customer spends points on, in place of
+        /// a promo code.
+        #[serde(default)]
+        reward: Option<i32>,
+    },
+    Ping,
+}
+
+/// Sent by the server to a kiosk.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum ServerMessage {
+    OrderAccepted {
+        order_number: u32,
+        total: Money,
+    },
+    OrderRejected {
+        reason: String,
+    },
+    /// The customer could not pay for the order, which was not placed.
+    PaymentDeclined {
+        reason: String,
+    },
+    /// Sent to every kiosk; each one follows the orders it placed.
+    OrderStatusChanged {
+        order_number: u32,
+        status: OrderStatus,
+    },
+    MenuUpdated(Config),
+    PromoAccepted(Promotion),
+    PromoRejected {
+        code: String,
+        reason: String,
+    },
+    GiftCardBalance {
+        code: String,
+        balance: Money,
+    },
+    GiftCardRejected {
+        code: String,
+        reason: String,
+    },
+    /// Customers the server has not seen before have no points yet.
+    LoyaltyBalance {
+        phone: String,
+        points: i64,
+    },
+    LoyaltyRejected {
+        phone: String,
+        reason: String,
+    },
+    Pong,
+}
+
+/// An option chosen for one item of a [`Ticket`].
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TicketModifier {
+    pub group: String,
+    pub name: String,
+    pub quantity: u32,
+}
+
+/// One item of a [`Ticket`].
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct TicketItem {
+    pub quantity: u32,
+    pub name: String,
+    pub modifiers: Vec<TicketModifier>,
+}
+
+impl TicketItem {
+    /// Describes `order`, which must have passed [`Order::validate`] against
+    /// `menu`.
+    pub fn new(quantity: u32, menu: &Menu, order: &Order) -> Self {
+        let item = menu.item(order.item);
+        let groups = item.into_iter().flat_map(|item| &item.modifier_groups);
+        TicketItem {
+            quantity,
+            name: item.map(|item| item.name.clone()).unwrap_or_default(),
+            modifiers: groups
+                .flat_map(|group| {
+                    order.chosen(group).filter_map(move |chosen| {
+                        group.option(chosen.id).map(|option| TicketModifier {
+                            group: group.name.clone(),
+                            name: option.name.clone(),
+                            quantity: chosen.quantity,
+                        })
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An accepted order as the kitchen needs to see it.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct Ticket {
+    pub order_id: i64,
+    pub order_number: u32,
+    pub items: Vec<TicketItem>,
+    pub status: OrderStatus,
+}
+
+impl Ticket {
+    /// Describes `cart`, which must have passed [`Cart::validate`] against
+    /// `menu`.
+    pub fn new(order_id: i64, order_number: u32, menu: &Menu, cart: &Cart) -> Self {
+        Ticket {
+            order_id,
+            order_number,
+            items: cart
+                .items
+                .iter()
+                .map(|item| TicketItem::new(item.quantity, menu, &item.order))
+                .collect(),
+            status: OrderStatus::Received,
+        }
+    }
+}
+
+/// Sent by a kitchen screen to the server.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum KitchenClientMessage {
+    /// Moves an order to `status`; once it is no longer open its ticket
+    /// leaves every kitchen screen. [`OrderStatus::Cancelled`] is refused,
+    /// since only a manager voiding the order gives the customer their money
+    /// back.
+    SetStatus { order_id: i64, status: OrderStatus },
+}
+
+/// Sent by the server to a kitchen screen.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum KitchenServerMessage {
+    /// Every open ticket, sent when a screen connects.
+    Tickets(Vec<Ticket>),
+    TicketAdded(Ticket),
+    StatusChanged {
+        order_id: i64,
+        status: OrderStatus,
+    },
+}
+
+/// An order as the pickup board shows it.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug)]
+pub struct BoardOrder {
+    pub order_number: u32,
+    pub status: OrderStatus,
+}
+
+/// Sent by the server to a pickup board. Boards never send anything back.
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub enum BoardServerMessage {
+    /// Every open order, sent when a board connects.
+    Orders(Vec<BoardOrder>),
+    /// Sent for new orders too, which arrive as [`OrderStatus::Received`].
+    OrderStatusChanged(BoardOrder),
+}
+
+#[derive(Deserialize, Serialize)]
+struct Envelope<M> {
+    version: u32,
+    message: M,
+}
+
+#[derive(Debug)]
+pub enum ProtocolError {
+    UnsupportedVersion(u32),
+    Malformed(serde_json::Error),
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProtocolError::UnsupportedVersion(version) => write!(
+                f,
+                "protocol version {version} is not supported (expected {PROTOCOL_VERSION})"
+            ),
+            ProtocolError::Malformed(err) => write!(f, "malformed message: {err}"),
+        }
+    }
+}
+
+impl Error for ProtocolError {}
+
+/// Encodes `message` as the text of a WebSocket frame.
+pub fn encode<M: Serialize>(message: &M) -> String {
+    serde_json::to_string(&Envelope {
+        version: PROTOCOL_VERSION,
+        message,
+    })
+    .expect("protocol messages always serialize")
+}
+
+/// Decodes the text of a WebSocket frame produced by [`encode`].
+pub fn decode<M: DeserializeOwned>(text: &str) -> Result<M, ProtocolError> {
+    let envelope: Envelope<serde_json::Value> =
+        serde_json::from_str(text).map_err(ProtocolError::Malformed)?;
+    if envelope.version != PROTOCOL_VERSION {
+        return Err(ProtocolError::UnsupportedVersion(envelope.version));
+    }
+    serde_json::from_value(envelope.message).map_err(ProtocolError::Malformed)
+}
@@ -0,0 +1,222 @@
+use std::{convert::TryFrom, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Money;
+
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Weekday {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl Weekday {
+    const ALL: [Weekday; 7] = [
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+        Weekday::Sunday,
+    ];
+
+    /// The weekday `days` days after Monday, wrapping around each week.
+    pub fn from_monday(days: u32) -> Self {
+        Weekday::ALL[days as usize % 7]
+    }
+
+    pub fn previous(self) -> Self {
+        Weekday::from_monday(self as u32 + 6)
+    }
+}
+
+/// A time of day to the minute, written as `"HH:MM"` in the menu config.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(u16);
+
+impl TimeOfDay {
+    pub fn new(hour: u8, minute: u8) -> Option<Self> {
+        (hour < 24 && minute < 60).then(|| TimeOfDay(u16::from(hour) * 60 + u16::from(minute)))
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = String;
+
+    fn try_from(time: String) -> Result<Self, Self::Error> {
+        let (hour, minute) = time
+            .split_once(':')
+            .and_then(|(hour, minute)| Some((hour.parse().ok()?, minute.parse().ok()?)))
+            .ok_or_else(|| format!("{time:?} is not a time of day like \"14:30\""))?;
+        TimeOfDay::new(hour, minute).ok_or_else(|| format!("{time:?} is not a time of day"))
+    }
+}
+
+impl From<TimeOfDay> for String {
+    fn from(time: TimeOfDay) -> String {
+        time.to_string()
+    }
+}
+
+impl fmt::Display for TimeOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+/// A moment on the server's clock, as far as schedules care.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LocalTime {
+    pub weekday: Weekday,
+    pub time: TimeOfDay,
+}
+
+/// When something on the menu is offered, from `start` until just before
+/// `end`. An `end` before `start` runs past midnight into the next day.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    /// The days the schedule starts on. Every day when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<Weekday>,
+    pub start: TimeOfDay,
+    pub end: TimeOfDay,
+}
+
+impl Schedule {
+    fn starts_on(&self, weekday: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&weekday)
+    }
+
+    pub fn contains(&self, now: LocalTime) -> bool {
+        if self.start <= self.end {
+            self.starts_on(now.weekday) && self.start <= now.time && now.time < self.end
+        } else {
+            (self.starts_on(now.weekday) && self.start <= now.time)
+                || (self.starts_on(now.weekday.previous()) && now.time < self.end)
+        }
+    }
+}
+
+/// Whether something with `schedules` is offered at `now`. Things without
+/// any schedules are always offered.
+pub fn is_scheduled(schedules: &[Schedule], now: LocalTime) -> bool {
+    schedules.is_empty() || schedules.iter().any(|schedule| schedule.contains(now))
+}
+
+/// A price that replaces an item's usual one while it is scheduled, like a
+/// happy hour.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct Special {
+    pub price: Money,
+    pub schedules: Vec<Schedule>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::*;
+
+    fn time(time: &str) -> TimeOfDay {
+        TimeOfDay::try_from(time.to_string()).unwrap()
+    }
+
+    fn at(weekday: Weekday, at: &str) -> LocalTime {
+        LocalTime {
+            weekday,
+            time: time(at),
+        }
+    }
+
+    fn schedule(days: Vec<Weekday>, start: &str, end: &str) -> Schedule {
+        Schedule {
+            days,
+            start: time(start),
+            end: time(end),
+        }
+    }
+
+    #[test]
+    fn reads_and_writes_times_of_day() {
+        assert_eq!(time("14:30"), TimeOfDay::new(14, 30).unwrap());
+        assert_eq!(time("9:05").to_string(), "09:05");
+        assert_eq!(time("00:00"), TimeOfDay::new(0, 0).unwrap());
+        for invalid in ["24:00", "12:60", "noon", "12", "12:-1", ""] {
+            assert!(
+                TimeOfDay::try_from(invalid.to_string()).is_err(),
+                "{}",
+                invalid
+            );
+        }
+    }
+
+    #[test]
+    fn weekdays_wrap_around() {
+        assert_eq!(Weekday::Tuesday.previous(), Weekday::Monday);
+        assert_eq!(Weekday::Monday.previous(), Weekday::Sunday);
+        assert_eq!(Weekday::from_monday(6), Weekday::Sunday);
+        assert_eq!(Weekday::from_monday(7), Weekday::Monday);
+    }
+
+    #[test]
+    fn schedules_run_until_just_before_their_end() {
+        let lunch = schedule(vec![Weekday::Monday], "11:00", "14:00");
+        assert!(!lunch.contains(at(Weekday::Monday, "10:59")));
+        assert!(lunch.contains(at(Weekday::Monday, "11:00")));
+        assert!(lunch.contains(at(Weekday::Monday, "13:59")));
+        assert!(!lunch.contains(at(Weekday::Monday, "14:00")));
+        assert!(!lunch.contains(at(Weekday::Tuesday, "12:00")));
+    }
+
+    #[test]
+    fn schedules_can_run_past_midnight() {
+        let late = schedule(vec![Weekday::Friday], "22:00", "02:00");
+        assert!(late.contains(at(Weekday::Friday, "23:30")));
+        assert!(late.contains(at(Weekday::Saturday, "01:59")));
+        assert!(!late.contains(at(Weekday::Saturday, "02:00")));
+        assert!(!late.contains(at(Weekday::Friday, "01:00")));
+        assert!(!late.contains(at(Weekday::Saturday, "23:00")));
+
+        // Sunday night runs on into Monday morning.
+        let sunday = schedule(vec![Weekday::Sunday], "22:00", "02:00");
+        assert!(sunday.contains(at(Weekday::Monday, "01:00")));
+
+        let nightly = schedule(Vec::new(), "22:00", "02:00");
+        assert!(nightly.contains(at(Weekday::Wednesday, "01:00")));
+        assert!(!nightly.contains(at(Weekday::Wednesday, "12:00")));
+    }
+
+    #[test]
+    fn menus_only_offer_what_is_scheduled() {
+        let mut config = config();
+        config.menu.categories[1].schedules = vec![schedule(Vec::new(), "11:00", "14:00")];
+
+        let menu = config.menu.at(at(Weekday::Monday, "12:00"));
+        assert!(menu.item(TEA).is_some());
+        let menu = config.menu.at(at(Weekday::Monday, "15:00"));
+        assert!(menu.item(TEA).is_none());
+        assert_eq!(menu.categories.len(), 1, "empty categories are left out");
+        assert!(menu.item(RAMEN).is_some());
+    }
+
+    #[test]
+    fn specials_replace_the_price_while_scheduled() {
+        let mut config = config();
+        config.menu.categories[0].items[0].specials = vec![Special {
+            price: Money::from_cents(800),
+            schedules: vec![schedule(vec![Weekday::Friday], "15:00", "17:00")],
+        }];
+
+        let price = |now| config.menu.at(now).item(RAMEN).unwrap().price;
+        assert_eq!(price(at(Weekday::Friday, "16:00")), Money::from_cents(800));
+        assert_eq!(price(at(Weekday::Friday, "17:00")), Money::from_cents(1000));
+        assert_eq!(price(at(Weekday::Monday, "16:00")), Money::from_cents(1000));
+    }
+}
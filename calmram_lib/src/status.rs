@@ -0,0 +1,177 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// Where an accepted order is on its way to the customer.
+///
+/// Orders move forward one step at a time, from `Received` to `PickedUp`,
+/// and can be cancelled until they are picked up.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    Received,
+    Preparing,
+    Ready,
+    PickedUp,
+    Cancelled,
+}
+
+impl OrderStatus {
+    /// The status that follows this one when the order goes well.
+    pub fn next(self) -> Option<OrderStatus> {
+        match self {
+            OrderStatus::Received => Some(OrderStatus::Preparing),
+            OrderStatus::Preparing => Some(OrderStatus::Ready),
+            OrderStatus::Ready => Some(OrderStatus::PickedUp),
+            OrderStatus::PickedUp | OrderStatus::Cancelled => None,
+        }
+    }
+
+    /// Whether the kitchen still has something to do for the order.
+    pub fn is_open(self) -> bool {
+        self.next().is_some()
+    }
+
+    pub fn can_become(self, status: OrderStatus) -> bool {
+        self.next() == Some(status) || (self.is_open() && status == OrderStatus::Cancelled)
+    }
+
+    pub fn transition(self, status: OrderStatus) -> Result<OrderStatus, InvalidTransition> {
+        if self.can_become(status) {
+            Ok(status)
+        } else {
+            Err(InvalidTransition {
+                from: self,
+                to: status,
+            })
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OrderStatus::Received => "received",
+            OrderStatus::Preparing => "preparing",
+            OrderStatus::Ready => "ready",
+            OrderStatus::PickedUp => "picked_up",
+            OrderStatus::Cancelled => "cancelled",
+        }
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrderStatus::Received => "Received",
+            OrderStatus::Preparing => "Preparing",
+            OrderStatus::Ready => "Ready",
+            OrderStatus::PickedUp => "Picked up",
+            OrderStatus::Cancelled => "Cancelled",
+        })
+    }
+}
+
+impl FromStr for OrderStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "received" => Ok(OrderStatus::Received),
+            "preparing" => Ok(OrderStatus::Preparing),
+            "ready" => Ok(OrderStatus::Ready),
+            "picked_up" => Ok(OrderStatus::PickedUp),
+            "cancelled" => Ok(OrderStatus::Cancelled),
+            _ => Err(format!("unknown order status {s:?}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InvalidTransition {
+    pub from: OrderStatus,
+    pub to: OrderStatus,
+}
+
+impl fmt::Display for InvalidTransition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "an order that is {} cannot become {}",
+            self.from.as_str(),
+            self.to.as_str()
+        )
+    }
+}
+
+impl Error for InvalidTransition {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [OrderStatus; 5] = [
+        OrderStatus::Received,
+        OrderStatus::Preparing,
+        OrderStatus::Ready,
+        OrderStatus::PickedUp,
+        OrderStatus::Cancelled,
+    ];
+
+    #[test]
+    fn orders_move_forward_one_step_at_a_time() {
+        use OrderStatus::*;
+        assert!(Received.can_become(Preparing));
+        assert!(Preparing.can_become(Ready));
+        assert!(Ready.can_become(PickedUp));
+
+        assert!(!Received.can_become(Ready));
+        assert!(!Ready.can_become(Preparing));
+        assert!(!Preparing.can_become(Preparing));
+        assert!(!PickedUp.can_become(Received));
+    }
+
+    #[test]
+    fn only_open_orders_can_be_cancelled() {
+        for status in ALL {
+            assert_eq!(
+                status.can_become(OrderStatus::Cancelled),
+                status.is_open(),
+                "{:?}",
+                status
+            );
+        }
+        assert!(OrderStatus::Ready.is_open());
+        assert!(!OrderStatus::PickedUp.is_open());
+        assert!(!OrderStatus::Cancelled.is_open());
+    }
+
+    #[test]
+    fn transitions_say_what_was_not_allowed() {
+        assert_eq!(
+            OrderStatus::Received.transition(OrderStatus::Preparing),
+            Ok(OrderStatus::Preparing)
+        );
+        let err = OrderStatus::PickedUp
+            .transition(OrderStatus::Cancelled)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            InvalidTransition {
+                from: OrderStatus::PickedUp,
+                to: OrderStatus::Cancelled,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "an order that is picked_up cannot become cancelled"
+        );
+    }
+
+    #[test]
+    fn statuses_read_back_what_they_write() {
+        for status in ALL {
+            assert_eq!(status.as_str().parse(), Ok(status));
+        }
+        assert!("Picked up".parse::<OrderStatus>().is_err());
+        assert!("".parse::<OrderStatus>().is_err());
+    }
+}
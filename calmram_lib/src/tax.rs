@@ -0,0 +1,386 @@
+use std::{convert::TryFrom, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Money;
+
+/// A percentage, kept exact to a thousandth of a percent so rates like
+/// 8.875% survive being written in the menu config as decimals.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(try_from = "f64", into = "f64")]
+pub struct Percent(u32);
+
+impl Percent {
+    pub const fn from_thousandths(thousandths: u32) -> Self {
+        Percent(thousandths)
+    }
+
+    pub const fn thousandths(self) -> u32 {
+        self.0
+    }
+
+    /// A percentage from 0 to 100 given in thousandths of a percent, or
+    /// `None` for anything outside that range.
+    pub fn checked_from_thousandths(thousandths: i32) -> Option<Self> {
+        u32::try_from(thousandths)
+            .ok()
+            .filter(|&thousandths| i128::from(thousandths) <= WHOLE)
+            .map(Percent)
+    }
+
+    /// This percentage of `amount`, rounded to the cent.
+    pub fn of(self, amount: Money, rounding: Rounding) -> Money {
+        let cents = i128::from(amount.cents()) * i128::from(self.0);
+        Money::from_cents(rounding.divide(cents, WHOLE) as i64)
+    }
+}
+
+impl TryFrom<f64> for Percent {
+    type Error = String;
+
+    fn try_from(percent: f64) -> Result<Self, Self::Error> {
+        if (0.0..=100.0).contains(&percent) {
+            Ok(Percent((percent * 1000.0).round() as u32))
+        } else {
+            Err(format!("{percent} is not a percentage from 0 to 100"))
+        }
+    }
+}
+
+impl From<Percent> for f64 {
+    fn from(percent: Percent) -> f64 {
+        f64::from(percent.0) / 1000.0
+    }
+}
+
+impl fmt::Display for Percent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fraction = format!("{:03}", self.0 % 1000);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            write!(f, "{}%", self.0 / 1000)
+        } else {
+            write!(f, "{}.{fraction}%", self.0 / 1000)
+        }
+    }
+}
+
+/// How tax is rounded to the cent.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Half a cent or more rounds up.
+    #[default]
+    HalfUp,
+    /// Exactly half a cent rounds to the even cent.
+    HalfEven,
+    Up,
+    Down,
+}
+
+impl Rounding {
+    /// Divides `amount` by `divisor`, rounding the magnitude of the result.
+    pub(crate) fn divide(self, amount: i128, divisor: i128) -> i128 {
+        let (quotient, remainder) = (amount.abs() / divisor, amount.abs() % divisor);
+        let round_up = match self {
+            Rounding::HalfUp => 2 * remainder >= divisor,
+            Rounding::HalfEven => {
+                2 * remainder > divisor || (2 * remainder == divisor && quotient % 2 == 1)
+            }
+            Rounding::Up => remainder > 0,
+            Rounding::Down => false,
+        };
+        (quotient + i128::from(round_up)) * amount.signum()
+    }
+}
+
+/// One tax charged on orders, such as state or city sales tax.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TaxRate {
+    pub name: String,
+    pub percent: Percent,
+    /// The names of the menu categories the tax applies to. Every category
+    /// when empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub categories: Vec<String>,
+}
+
+impl TaxRate {
+    pub fn applies_to(&self, category: &str) -> bool {
+        self.categories.is_empty() || self.categories.iter().any(|name| name == category)
+    }
+}
+
+/// The `tax` section of the menu config. No tax is charged without one.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TaxConfig {
+    /// Whether menu prices already include tax, rather than tax being added
+    /// on top of them.
+    #[serde(default)]
+    pub inclusive: bool,
+    #[serde(default)]
+    pub rounding: Rounding,
+    #[serde(default)]
+    pub rates: Vec<TaxRate>,
+}
+
+/// The tax charged at one [`TaxRate`].
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+pub struct TaxLine {
+    pub name: String,
+    pub percent: Percent,
+    pub amount: Money,
+}
+
+/// Thousandths of a percent in a whole.
+const WHOLE: i128 = 100_000;
+
+impl TaxConfig {
+    /// Works out each tax on `prices`, given as the menu category and price
+    /// of every item ordered. Each tax is rounded once, over the whole order.
+    pub fn apply(&self, prices: &[(&str, Money)]) -> Vec<TaxLine> {
+        self.rates
+            .iter()
+            .map(|rate| {
+                // The tax in thousandths of a percent of a cent, so only the
+                // final amount is rounded.
+                let amount: i128 = prices
+                    .iter()
+                    .filter(|(category, _)| rate.applies_to(category))
+                    .map(|(category, price)| {
+                        let tax = i128::from(price.cents()) * i128::from(rate.percent.0);
+                        if self.inclusive {
+                            // Prices include every tax on them, so take out
+                            // this tax's share of the lot.
+                            let total: u32 = self
+                                .rates
+                                .iter()
+                                .filter(|rate| rate.applies_to(category))
+                                .map(|rate| rate.percent.0)
+                                .sum();
+                            tax * WHOLE / (WHOLE + i128::from(total))
+                        } else {
+                            tax
+                        }
+                    })
+                    .sum();
+                let cents = self.rounding.divide(amount, WHOLE);
+                TaxLine {
+                    name: rate.name.clone(),
+                    percent: rate.percent,
+                    amount: Money::from_cents(cents as i64),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{cart, config, order, RAMEN, SHOYU, TEA},
+        quote, Discount, Promotion,
+    };
+
+    const MODES: [Rounding; 4] = [
+        Rounding::HalfUp,
+        Rounding::HalfEven,
+        Rounding::Up,
+        Rounding::Down,
+    ];
+
+    fn divide(amount: i128, divisor: i128) -> [i128; 4] {
+        MODES.map(|rounding| rounding.divide(amount, divisor))
+    }
+
+    fn rate(name: &str, thousandths: u32, categories: &[&str]) -> TaxRate {
+        TaxRate {
+            name: name.to_string(),
+            percent: Percent::from_thousandths(thousandths),
+            categories: categories.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    fn cents(cents: i64) -> Money {
+        Money::from_cents(cents)
+    }
+
+    fn amounts(tax: &TaxConfig, prices: &[(&str, Money)]) -> Vec<i64> {
+        tax.apply(prices)
+            .iter()
+            .map(|line| line.amount.cents())
+            .collect()
+    }
+
+    #[test]
+    fn rounds_each_way() {
+        // [HalfUp, HalfEven, Up, Down]
+        assert_eq!(divide(20, 10), [2, 2, 2, 2]);
+        assert_eq!(divide(21, 10), [2, 2, 3, 2]);
+        assert_eq!(divide(25, 10), [3, 2, 3, 2]);
+        assert_eq!(divide(35, 10), [4, 4, 4, 3]);
+        assert_eq!(divide(29, 10), [3, 3, 3, 2]);
+        assert_eq!(divide(0, 10), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn rounds_the_magnitude_of_negative_amounts() {
+        assert_eq!(divide(-25, 10), [-3, -2, -3, -2]);
+        assert_eq!(divide(-21, 10), [-2, -2, -3, -2]);
+    }
+
+    #[test]
+    fn reads_and_shows_percentages() {
+        assert_eq!(
+            Percent::try_from(8.875),
+            Ok(Percent::from_thousandths(8875))
+        );
+        assert_eq!(
+            Percent::try_from(100.0),
+            Ok(Percent::from_thousandths(100_000))
+        );
+        assert!(Percent::try_from(100.5).is_err());
+        assert!(Percent::try_from(-1.0).is_err());
+        assert!(Percent::try_from(f64::NAN).is_err());
+
+        assert_eq!(Percent::from_thousandths(8875).to_string(), "8.875%");
+        assert_eq!(Percent::from_thousandths(8500).to_string(), "8.5%");
+        assert_eq!(Percent::from_thousandths(15_000).to_string(), "15%");
+    }
+
+    #[test]
+    fn checks_thousandths_are_a_percentage() {
+        assert_eq!(
+            Percent::checked_from_thousandths(100_000),
+            Some(Percent::from_thousandths(100_000))
+        );
+        assert_eq!(
+            Percent::checked_from_thousandths(0),
+            Some(Percent::from_thousandths(0))
+        );
+        assert_eq!(Percent::checked_from_thousandths(100_001), None);
+        assert_eq!(Percent::checked_from_thousandths(-1), None);
+        assert_eq!(Percent::checked_from_thousandths(i32::MIN), None);
+    }
+
+    #[test]
+    fn adds_exclusive_tax_rounded_as_configured() {
+        let mut tax = TaxConfig {
+            rates: vec![rate("Sales tax", 8875, &[])],
+            ..TaxConfig::default()
+        };
+        let prices = [("Ramen", cents(1000))];
+        let rounded: Vec<_> = MODES
+            .iter()
+            .map(|&rounding| {
+                tax.rounding = rounding;
+                amounts(&tax, &prices)[0]
+            })
+            .collect();
+        // $0.8875
+        assert_eq!(rounded, [89, 89, 89, 88]);
+
+        // Exactly half a cent.
+        tax.rates = vec![rate("Sales tax", 10_000, &[])];
+        let rounded: Vec<_> = MODES
+            .iter()
+            .map(|&rounding| {
+                tax.rounding = rounding;
+                amounts(&tax, &[("Ramen", cents(5))])[0]
+            })
+            .collect();
+        assert_eq!(rounded, [1, 0, 1, 0]);
+    }
+
+    #[test]
+    fn rounds_once_over_the_whole_order() {
+        let tax = TaxConfig {
+            rates: vec![rate("Sales tax", 10_000, &[])],
+            rounding: Rounding::Down,
+            ..TaxConfig::default()
+        };
+        // Half a cent on each, which would round to nothing one at a time.
+        assert_eq!(
+            amounts(&tax, &[("Ramen", cents(5)), ("Drinks", cents(5))]),
+            [1]
+        );
+    }
+
+    #[test]
+    fn taxes_only_the_categories_listed() {
+        let tax = TaxConfig {
+            rates: vec![
+                rate("State", 5000, &[]),
+                rate("Drinks tax", 10_000, &["Drinks"]),
+            ],
+            ..TaxConfig::default()
+        };
+        let prices = [("Ramen", cents(1000)), ("Drinks", cents(200))];
+        assert_eq!(amounts(&tax, &prices), [60, 20]);
+        assert!(!tax.rates[1].applies_to("Ramen"));
+    }
+
+    #[test]
+    fn takes_inclusive_tax_out_of_the_price() {
+        let tax = TaxConfig {
+            inclusive: true,
+            rates: vec![rate("Sales tax", 9000, &[])],
+            ..TaxConfig::default()
+        };
+        assert_eq!(amounts(&tax, &[("Ramen", cents(1090))]), [90]);
+
+        // Each rate's share of the tax a price includes.
+        let tax = TaxConfig {
+            inclusive: true,
+            rates: vec![rate("State", 5000, &[]), rate("City", 4000, &[])],
+            ..TaxConfig::default()
+        };
+        assert_eq!(amounts(&tax, &[("Ramen", cents(1090))]), [50, 40]);
+    }
+
+    fn taxed_quote(inclusive: bool, discount: Discount) -> crate::Quote {
+        let mut config = config();
+        config.tax = TaxConfig {
+            inclusive,
+            rates: vec![rate("Ramen tax", 10_000, &["Ramen"])],
+            ..TaxConfig::default()
+        };
+        let promotion = Promotion {
+            id: 1,
+            code: "TEST".to_string(),
+            name: "Test".to_string(),
+            discount,
+        };
+        let cart = cart(vec![(order(RAMEN, &[SHOYU]), 1), (order(TEA, &[]), 1)]);
+        quote(&config, &cart, Some(&promotion), None).unwrap()
+    }
+
+    #[test]
+    fn taxes_items_after_their_share_of_the_discount() {
+        // $5.00 off comes off the ramen, the only taxed item.
+        let amount_off = Discount::AmountOff { amount: cents(500) };
+        let quote = taxed_quote(false, amount_off);
+        assert_eq!((quote.tax, quote.before_tax()), (cents(50), cents(700)));
+        assert_eq!(quote.total, cents(750));
+
+        // Free tea leaves the ramen's tax as it was.
+        let quote = taxed_quote(false, Discount::FreeItem { item: TEA });
+        assert_eq!((quote.tax, quote.total), (cents(100), cents(1100)));
+
+        let half = Percent::from_thousandths(50_000);
+        let quote = taxed_quote(false, Discount::PercentOff { percent: half });
+        assert_eq!((quote.tax, quote.total), (cents(50), cents(650)));
+    }
+
+    #[test]
+    fn inclusive_tax_is_part_of_the_discounted_total() {
+        let amount_off = Discount::AmountOff { amount: cents(450) };
+        let quote = taxed_quote(true, amount_off);
+        // $5.50 of ramen includes $0.50 of tax.
+        assert!(quote.tax_inclusive);
+        assert_eq!(quote.tax, cents(50));
+        assert_eq!(quote.total, cents(750));
+        assert_eq!(quote.before_tax(), cents(700));
+    }
+}
@@ -0,0 +1,117 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Money, OrderError};
+
+/// One way a customer pays for part of an order.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Tender {
+    /// Paid at the kiosk's card terminal.
+    Card { amount: Money },
+    /// Paid in cash at the counter when the order is collected.
+    Cash { amount: Money },
+    /// Paid from the balance of the gift card with `code`.
+    GiftCard { code: String, amount: Money },
+}
+
+impl Tender {
+    pub fn amount(&self) -> Money {
+        match self {
+            Tender::Card { amount } | Tender::Cash { amount } | Tender::GiftCard { amount, .. } => {
+                *amount
+            }
+        }
+    }
+}
+
+/// Checks that every one of `tenders` is for some money and that together
+/// they pay exactly `total`.
+pub fn check_tenders(tenders: &[Tender], total: Money) -> Result<(), OrderError> {
+    if tenders.iter().any(|tender| tender.amount() <= Money::ZERO) {
+        return Err(OrderError::EmptyTender);
+    }
+    let tendered = tenders.iter().map(Tender::amount).sum();
+    if tendered != total {
+        return Err(OrderError::WrongTenderTotal { total, tendered });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn card(cents: i64) -> Tender {
+        Tender::Card {
+            amount: Money::from_cents(cents),
+        }
+    }
+
+    fn cash(cents: i64) -> Tender {
+        Tender::Cash {
+            amount: Money::from_cents(cents),
+        }
+    }
+
+    fn total(cents: i64) -> Money {
+        Money::from_cents(cents)
+    }
+
+    #[test]
+    fn accepts_tenders_that_pay_the_total() {
+        assert_eq!(check_tenders(&[card(1200)], total(1200)), Ok(()));
+        assert_eq!(check_tenders(&[card(700), cash(500)], total(1200)), Ok(()));
+        let gift_card = Tender::GiftCard {
+            code: "GIFT".to_string(),
+            amount: Money::from_cents(200),
+        };
+        assert_eq!(gift_card.amount(), Money::from_cents(200));
+        assert_eq!(check_tenders(&[gift_card, card(1000)], total(1200)), Ok(()));
+    }
+
+    #[test]
+    fn accepts_partial_approvals_made_up_on_other_cards() {
+        // A $12.00 card only had $5.00 approved, so the rest went on two
+        // more cards.
+        let tenders = [card(500), card(400), card(300)];
+        assert_eq!(check_tenders(&tenders, total(1200)), Ok(()));
+    }
+
+    #[test]
+    fn rejects_partial_approvals_left_short() {
+        assert_eq!(
+            check_tenders(&[card(500)], total(1200)),
+            Err(OrderError::WrongTenderTotal {
+                total: total(1200),
+                tendered: Money::from_cents(500)
+            })
+        );
+        assert_eq!(
+            check_tenders(&[card(800), cash(500)], total(1200)),
+            Err(OrderError::WrongTenderTotal {
+                total: total(1200),
+                tendered: Money::from_cents(1300)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_tenders_for_nothing() {
+        assert_eq!(
+            check_tenders(&[card(1200), cash(0)], total(1200)),
+            Err(OrderError::EmptyTender)
+        );
+        // A negative tender cannot make up for an overpayment.
+        assert_eq!(
+            check_tenders(&[card(1500), cash(-300)], total(1200)),
+            Err(OrderError::EmptyTender)
+        );
+        assert_eq!(
+            check_tenders(&[], total(1200)),
+            Err(OrderError::WrongTenderTotal {
+                total: total(1200),
+                tendered: Money::ZERO
+            })
+        );
+    }
+}
@@ -0,0 +1,150 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Money, Percent, Rounding};
+
+/// What a percentage tip is worked out on.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TipBase {
+    /// The order after any discount, without tax.
+    #[default]
+    BeforeTax,
+    /// The order after any discount, with tax.
+    AfterTax,
+}
+
+/// The `tip` section of the menu config. Customers are not asked for a tip
+/// without one.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct TipConfig {
+    /// The percentages offered as buttons, such as 15, 18 and 20.
+    #[serde(default)]
+    pub presets: Vec<Percent>,
+    /// Whether customers can enter an amount of their own.
+    #[serde(default)]
+    pub custom: bool,
+    #[serde(default)]
+    pub base: TipBase,
+    #[serde(default)]
+    pub rounding: Rounding,
+}
+
+impl TipConfig {
+    /// Whether customers are asked for a tip at all.
+    pub fn enabled(&self) -> bool {
+        !self.presets.is_empty() || self.custom
+    }
+
+    /// Whether customers can leave `tip`.
+    pub fn offers(&self, tip: Tip) -> bool {
+        match tip {
+            Tip::Percent { percent } => self.presets.contains(&percent),
+            Tip::Amount { amount } => self.custom && amount >= Money::ZERO,
+        }
+    }
+
+    /// What `tip` comes to on an order costing `base`, worked out as set by
+    /// [`TipConfig::base`].
+    pub fn amount(&self, tip: Tip, base: Money) -> Money {
+        match tip {
+            Tip::Percent { percent } => percent.of(base, self.rounding),
+            Tip::Amount { amount } => amount,
+        }
+    }
+}
+
+/// The tip a customer chose to leave.
+#[derive(Deserialize, Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Tip {
+    /// One of the [`TipConfig::presets`].
+    Percent { percent: Percent },
+    /// An amount the customer entered.
+    Amount { amount: Money },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{cart, config, order, RAMEN, SHOYU},
+        quote, Config, OrderError, Quote, TaxConfig, TaxRate,
+    };
+
+    fn percent(percent: u32) -> Tip {
+        Tip::Percent {
+            percent: Percent::from_thousandths(percent * 1000),
+        }
+    }
+
+    fn tipping(base: TipBase, inclusive: bool) -> Config {
+        let mut config = config();
+        config.tip = TipConfig {
+            presets: vec![Percent::from_thousandths(15_000)],
+            custom: true,
+            base,
+            rounding: Rounding::HalfUp,
+        };
+        config.tax = TaxConfig {
+            inclusive,
+            rates: vec![TaxRate {
+                name: "Sales tax".to_string(),
+                percent: Percent::from_thousandths(10_000),
+                categories: Vec::new(),
+            }],
+            ..TaxConfig::default()
+        };
+        config
+    }
+
+    /// A $11.00 bowl, with `tip`.
+    fn tipped(config: &Config, tip: Tip) -> Result<Quote, OrderError> {
+        let cart = cart(vec![(order(RAMEN, &[SHOYU]), 1)]);
+        let mut config = config.clone();
+        config.menu.categories[0].items[0].price = Money::from_cents(1100);
+        quote(&config, &cart, None, Some(tip))
+    }
+
+    #[test]
+    fn tips_on_the_order_before_tax() {
+        let quote = tipped(&tipping(TipBase::BeforeTax, false), percent(15)).unwrap();
+        // 15% of $11.00 is $1.65, on top of $1.10 of tax.
+        assert_eq!(quote.tip, Money::from_cents(165));
+        assert_eq!(quote.total, Money::from_cents(1100 + 110 + 165));
+
+        // Prices that include tax are tipped on what is left without it.
+        let quote = tipped(&tipping(TipBase::BeforeTax, true), percent(15)).unwrap();
+        assert_eq!(quote.tax, Money::from_cents(100));
+        assert_eq!(quote.tip, Money::from_cents(150));
+        assert_eq!(quote.total, Money::from_cents(1100 + 150));
+    }
+
+    #[test]
+    fn tips_on_the_order_after_tax() {
+        let quote = tipped(&tipping(TipBase::AfterTax, false), percent(15)).unwrap();
+        // 15% of $12.10 is $1.815.
+        assert_eq!(quote.tip, Money::from_cents(182));
+
+        let quote = tipped(&tipping(TipBase::AfterTax, true), percent(15)).unwrap();
+        assert_eq!(quote.tip, Money::from_cents(165));
+    }
+
+    #[test]
+    fn offers_only_the_configured_tips() {
+        let config = tipping(TipBase::BeforeTax, false);
+        let amount = |cents| Tip::Amount {
+            amount: Money::from_cents(cents),
+        };
+        assert!(tipped(&config, amount(300)).is_ok());
+        assert_eq!(tipped(&config, percent(18)), Err(OrderError::TipNotOffered));
+        assert_eq!(tipped(&config, amount(-1)), Err(OrderError::TipNotOffered));
+
+        let mut presets_only = config;
+        presets_only.tip.custom = false;
+        assert_eq!(
+            tipped(&presets_only, amount(300)),
+            Err(OrderError::TipNotOffered)
+        );
+        assert!(!TipConfig::default().enabled());
+    }
+}
@@ -0,0 +1,747 @@
+use std::{collections::HashSet, error::Error, fmt};
+
+use crate::{
+    Cart, CartItem, ChosenOption, Config, GroupStyle, ItemId, Menu, MenuItem, ModifierGroup, Money,
+    OptionId, Order,
+};
+
+/// Why an [`Order`] or [`Cart`] cannot be made from a [`Menu`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OrderError {
+    EmptyCart,
+    ZeroQuantity,
+    /// More than [`crate::CartItem::MAX_QUANTITY`] of one item.
+    TooManyOfItem {
+        max: u32,
+    },
+    UnknownItem(ItemId),
+    UnknownOption(OptionId),
+    DuplicateOption(OptionId),
+    OptionQuantity {
+        id: OptionId,
+        max: u32,
+    },
+    TooFewOptions {
+        group: String,
+        min: u32,
+    },
+    TooManyOptions {
+        group: String,
+        max: u32,
+    },
+    SoldOut(i32),
+    TipNotOffered,
+    EmptyTender,
+    WrongTenderTotal {
+        total: Money,
+        tendered: Money,
+    },
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::EmptyCart => write!(f, "the cart is empty"),
+            OrderError::ZeroQuantity => write!(f, "an item was ordered zero times"),
+            OrderError::TooManyOfItem { max } => {
+                write!(f, "an item can be ordered at most {max} times at once")
+            }
+            OrderError::UnknownItem(id) => write!(f, "item {id} is not on the menu"),
+            OrderError::UnknownOption(id) => write!(f, "option {id} is not offered"),
+            OrderError::DuplicateOption(id) => {
+                write!(f, "option {id} was chosen more than once")
+            }
+            OrderError::OptionQuantity { id, max } => {
+                write!(f, "option {id} can only be chosen 1 to {max} times")
+            }
+            OrderError::TooFewOptions { group, min } => {
+                write!(f, "{group} needs at least {min} chosen")
+            }
+            OrderError::TooManyOptions { group, max } => {
+                write!(f, "{group} allows at most {max} chosen")
+            }
+            OrderError::SoldOut(id) => write!(f, "item {id} is sold out"),
+            OrderError::TipNotOffered => write!(f, "that tip is not offered"),
+            OrderError::EmptyTender => write!(f, "a payment was for nothing"),
+            OrderError::WrongTenderTotal { total, tendered } => {
+                write!(f, "the payments add up to {tendered} instead of {total}")
+            }
+        }
+    }
+}
+
+impl Error for OrderError {}
+
+impl Order {
+    /// Checks that the item and every option in this order are on `menu`,
+    /// within their groups' limits and not sold out.
+    pub fn validate(&self, menu: &Menu) -> Result<(), OrderError> {
+        self.validate_items(menu)?;
+
+        let option_ids = self.options.iter().map(|chosen| chosen.id);
+        match std::iter::once(self.item)
+            .chain(option_ids)
+            .find(|id| !menu.is_available(*id))
+        {
+            Some(id) => Err(OrderError::SoldOut(id)),
+            None => Ok(()),
+        }
+    }
+
+    fn validate_items(&self, menu: &Menu) -> Result<(), OrderError> {
+        let item = menu
+            .item(self.item)
+            .ok_or(OrderError::UnknownItem(self.item))?;
+
+        let mut seen = HashSet::new();
+        for chosen in &self.options {
+            let option = item
+                .option(chosen.id)
+                .ok_or(OrderError::UnknownOption(chosen.id))?;
+            if !seen.insert(chosen.id) {
+                return Err(OrderError::DuplicateOption(chosen.id));
+            }
+            if chosen.quantity == 0 || chosen.quantity > option.max_quantity {
+                return Err(OrderError::OptionQuantity {
+                    id: chosen.id,
+                    max: option.max_quantity,
+                });
+            }
+        }
+
+        for group in &item.modifier_groups {
+            let chosen = self.chosen_count(group);
+            if chosen < group.min {
+                return Err(OrderError::TooFewOptions {
+                    group: group.name.clone(),
+                    min: group.min,
+                });
+            }
+            if let Some(max) = group.max.filter(|max| chosen > *max) {
+                return Err(OrderError::TooManyOptions {
+                    group: group.name.clone(),
+                    max,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The options of this order that come from `group`.
+    pub fn chosen<'a>(
+        &'a self,
+        group: &'a ModifierGroup,
+    ) -> impl Iterator<Item = &'a ChosenOption> + 'a {
+        self.options
+            .iter()
+            .filter(move |chosen| group.option(chosen.id).is_some())
+    }
+
+    /// How many options were chosen from `group`, counting each extra
+    /// helping of an option.
+    pub fn chosen_count(&self, group: &ModifierGroup) -> u32 {
+        self.chosen(group).map(|chosen| chosen.quantity).sum()
+    }
+
+    /// How many of option `id` were chosen, or 0 if it was not.
+    pub fn quantity(&self, id: OptionId) -> u32 {
+        self.options
+            .iter()
+            .find(|chosen| chosen.id == id)
+            .map_or(0, |chosen| chosen.quantity)
+    }
+
+    pub fn is_chosen(&self, id: OptionId) -> bool {
+        self.quantity(id) > 0
+    }
+
+    /// Picks or unpicks option `id` the way a customer tapping it would
+    /// expect. In a single choice group a new option replaces the current
+    /// one; otherwise options are toggled, unless that would go past the
+    /// group's limits.
+    pub fn toggle(&mut self, menu: &Menu, id: OptionId) {
+        if self.is_chosen(id) {
+            self.set_quantity(menu, id, 0);
+        } else {
+            self.add(menu, id);
+        }
+    }
+
+    /// Adds one more of option `id`, up to its `max_quantity`. Like
+    /// [`Order::toggle`], a new option replaces the current choice of a
+    /// single choice group.
+    pub fn add(&mut self, menu: &Menu, id: OptionId) {
+        let Some(group) = menu.item(self.item).and_then(|item| item.group_of(id)) else {
+            return;
+        };
+
+        if group.is_single_choice() && !self.is_chosen(id) {
+            self.options
+                .retain(|chosen| group.option(chosen.id).is_none());
+            self.options.push(ChosenOption { id, quantity: 1 });
+        } else {
+            self.set_quantity(menu, id, self.quantity(id) + 1);
+        }
+    }
+
+    /// Takes away one of option `id`, unpicking it after the last one.
+    pub fn remove(&mut self, menu: &Menu, id: OptionId) {
+        let quantity = self.quantity(id);
+        if quantity > 0 {
+            self.set_quantity(menu, id, quantity - 1);
+        }
+    }
+
+    /// Sets how many of option `id` are chosen, leaving the order as it is
+    /// if that would break the option's or its group's limits.
+    fn set_quantity(&mut self, menu: &Menu, id: OptionId, quantity: u32) {
+        let Some(group) = menu.item(self.item).and_then(|item| item.group_of(id)) else {
+            return;
+        };
+        let Some(option) = group.option(id) else {
+            return;
+        };
+
+        let count = self.chosen_count(group) - self.quantity(id) + quantity;
+        if quantity > option.max_quantity
+            || count < group.min
+            || group.max.is_some_and(|max| count > max)
+        {
+            return;
+        }
+
+        match self.options.iter().position(|chosen| chosen.id == id) {
+            Some(index) if quantity == 0 => {
+                self.options.remove(index);
+            }
+            Some(index) => self.options[index].quantity = quantity,
+            None if quantity > 0 => self.options.push(ChosenOption { id, quantity }),
+            None => {}
+        }
+    }
+
+    /// Brings an order in progress in line with a new menu. Options that are
+    /// no longer offered or have sold out are dropped, and groups left short
+    /// are filled with their default options, then with the first ones still
+    /// available. An item that was removed or sold out is swapped for the
+    /// first item still available.
+    pub fn reconcile(&mut self, menu: &Menu) {
+        let item = match menu.item(self.item).filter(|item| !item.sold_out) {
+            Some(item) => item,
+            None => {
+                let Some(item) = menu.items().find(|item| !item.sold_out) else {
+                    return;
+                };
+                *self = menu.new_order(item.id);
+                return;
+            }
+        };
+
+        let mut seen = HashSet::new();
+        self.options
+            .retain_mut(|chosen| match item.option(chosen.id) {
+                Some(option) if !option.sold_out && seen.insert(chosen.id) => {
+                    chosen.quantity = chosen.quantity.clamp(1, option.max_quantity);
+                    true
+                }
+                _ => false,
+            });
+
+        for group in &item.modifier_groups {
+            if let Some(max) = group.max {
+                // Trim the most recent choices until the group is back in
+                // its limit.
+                while self.chosen_count(group) > max {
+                    let last = self
+                        .options
+                        .iter()
+                        .rposition(|chosen| group.option(chosen.id).is_some());
+                    let Some(last) = last else {
+                        break;
+                    };
+                    let chosen = &mut self.options[last];
+                    if chosen.quantity > 1 {
+                        chosen.quantity -= 1;
+                    } else {
+                        self.options.remove(last);
+                    }
+                }
+            }
+
+            let defaults = group.options.iter().filter(|option| option.default);
+            let fallbacks = defaults.chain(&group.options);
+            for option in fallbacks.filter(|option| !option.sold_out) {
+                if self.chosen_count(group) >= group.min {
+                    break;
+                }
+                if !self.is_chosen(option.id) {
+                    self.options.push(ChosenOption {
+                        id: option.id,
+                        quantity: 1,
+                    });
+                }
+            }
+        }
+    }
+}
+
+impl Cart {
+    /// Checks that the cart has something in it and that every item can be
+    /// made from `menu`.
+    pub fn validate(&self, menu: &Menu) -> Result<(), OrderError> {
+        if self.is_empty() {
+            return Err(OrderError::EmptyCart);
+        }
+        for item in &self.items {
+            if item.quantity == 0 {
+                return Err(OrderError::ZeroQuantity);
+            }
+            if item.quantity > CartItem::MAX_QUANTITY {
+                return Err(OrderError::TooManyOfItem {
+                    max: CartItem::MAX_QUANTITY,
+                });
+            }
+            item.order.validate(menu)?;
+        }
+        Ok(())
+    }
+
+    /// Brings everything in the cart in line with a new menu. Items that are
+    /// no longer offered or have sold out are taken out, and the options of
+    /// the rest are reconciled as in [`Order::reconcile`].
+    pub fn reconcile(&mut self, menu: &Menu) {
+        self.items.retain(|item| {
+            menu.item(item.order.item)
+                .is_some_and(|menu_item| !menu_item.sold_out)
+        });
+        for item in &mut self.items {
+            item.order.reconcile(menu);
+        }
+    }
+}
+
+/// Why a [`Config`] cannot be served to kiosks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    NoItems,
+    EmptyCategory(String),
+    DuplicateId(i32),
+    /// A group's `min` is above its `max` or above how many options it has.
+    GroupLimits(String),
+    /// A [`GroupStyle::Scale`] group that is not single choice.
+    ScaleNotSingleChoice(String),
+    ZeroMaxQuantity(OptionId),
+    /// An allowance of a group keyed by an option that is not in another
+    /// group of the same item.
+    UnknownAllowanceOption {
+        group: String,
+        option: OptionId,
+    },
+    /// A group whose allowances are keyed by options of more than one
+    /// group, or of a group that is not single choice.
+    AllowanceGroups(String),
+    /// A special price with no schedules would never apply.
+    UnscheduledSpecial(ItemId),
+    UnknownTaxCategory {
+        tax: String,
+        category: String,
+    },
+    DefaultOrder {
+        item: ItemId,
+        err: OrderError,
+    },
+    DuplicateReward(i32),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::NoItems => write!(f, "the menu has no items"),
+            ConfigError::EmptyCategory(category) => {
+                write!(f, "the {category} category has no items")
+            }
+            ConfigError::DuplicateId(id) => write!(f, "more than one menu entry has id {id}"),
+            ConfigError::GroupLimits(group) => {
+                write!(f, "the {group} group asks for more options than it allows")
+            }
+            ConfigError::ScaleNotSingleChoice(group) => {
+                write!(f, "the {group} group is a scale but not single choice")
+            }
+            ConfigError::ZeroMaxQuantity(id) => {
+                write!(f, "option {id} has a max_quantity of 0")
+            }
+            ConfigError::UnknownAllowanceOption { group, option } => {
+                write!(
+                    f,
+                    "the {group} group has an allowance for option {option}, \
+                     which is not in another group of its item"
+                )
+            }
+            ConfigError::AllowanceGroups(group) => {
+                write!(
+                    f,
+                    "the allowances of the {group} group must all be for options \
+                     of one single choice group"
+                )
+            }
+            ConfigError::UnscheduledSpecial(item) => {
+                write!(f, "item {item} has a special price without schedules")
+            }
+            ConfigError::UnknownTaxCategory { tax, category } => {
+                write!(
+                    f,
+                    "{tax} applies to {category}, which is not a menu category"
+                )
+            }
+            ConfigError::DefaultOrder { item, err } => {
+                write!(f, "the default order of item {item} is invalid: {err}")
+            }
+            ConfigError::DuplicateReward(id) => {
+                write!(f, "more than one loyalty reward has id {id}")
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {}
+
+fn validate_group(group: &ModifierGroup) -> Result<(), ConfigError> {
+    let options = group.options.len() as u32;
+    if group.min > group.max.unwrap_or(options).min(options) {
+        return Err(ConfigError::GroupLimits(group.name.clone()));
+    }
+    if group.style == GroupStyle::Scale && !group.is_single_choice() {
+        return Err(ConfigError::ScaleNotSingleChoice(group.name.clone()));
+    }
+    if let Some(option) = group.options.iter().find(|option| option.max_quantity == 0) {
+        return Err(ConfigError::ZeroMaxQuantity(option.id));
+    }
+    Ok(())
+}
+
+/// Checks that the allowances of `group` are keyed by options of a single
+/// other group of `item`, one that only lets one of them be chosen.
+fn validate_allowances(item: &MenuItem, group: &ModifierGroup) -> Result<(), ConfigError> {
+    let mut keyed_by: Option<&ModifierGroup> = None;
+    for &option in group.allowances.keys() {
+        let owner = item
+            .group_of(option)
+            .filter(|owner| !std::ptr::eq(*owner, group))
+            .ok_or_else(|| ConfigError::UnknownAllowanceOption {
+                group: group.name.clone(),
+                option,
+            })?;
+        if !owner.is_single_choice()
+            || keyed_by.is_some_and(|keyed_by| !std::ptr::eq(keyed_by, owner))
+        {
+            return Err(ConfigError::AllowanceGroups(group.name.clone()));
+        }
+        keyed_by = Some(owner);
+    }
+    Ok(())
+}
+
+fn validate_default_order(menu: &Menu, item: &MenuItem) -> Result<(), ConfigError> {
+    let options = item.options().filter(|option| option.default);
+    let order = Order {
+        item: item.id,
+        options: options
+            .map(|option| ChosenOption {
+                id: option.id,
+                quantity: 1,
+            })
+            .collect(),
+    };
+    // Kiosks leave sold-out options out of new orders, so only the
+    // structure is checked here.
+    order
+        .validate_items(menu)
+        .map_err(|err| ConfigError::DefaultOrder { item: item.id, err })
+}
+
+impl Config {
+    /// Checks that the menu is usable and the default order of every item
+    /// can be made from it.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.menu.items().next().is_none() {
+            return Err(ConfigError::NoItems);
+        }
+        if let Some(category) = self.menu.categories.iter().find(|c| c.items.is_empty()) {
+            return Err(ConfigError::EmptyCategory(category.name.clone()));
+        }
+
+        let mut ids = HashSet::new();
+        for item in self.menu.items() {
+            let option_ids = item.options().map(|option| option.id);
+            for id in std::iter::once(item.id).chain(option_ids) {
+                if !ids.insert(id) {
+                    return Err(ConfigError::DuplicateId(id));
+                }
+            }
+        }
+
+        for item in self.menu.items() {
+            for group in &item.modifier_groups {
+                validate_group(group)?;
+                validate_allowances(item, group)?;
+            }
+            validate_default_order(&self.menu, item)?;
+            if item
+                .specials
+                .iter()
+                .any(|special| special.schedules.is_empty())
+            {
+                return Err(ConfigError::UnscheduledSpecial(item.id));
+            }
+        }
+
+        for rate in &self.tax.rates {
+            let unknown = rate.categories.iter().find(|name| {
+                !self
+                    .menu
+                    .categories
+                    .iter()
+                    .any(|category| category.name == **name)
+            });
+            if let Some(category) = unknown {
+                return Err(ConfigError::UnknownTaxCategory {
+                    tax: rate.name.clone(),
+                    category: category.clone(),
+                });
+            }
+        }
+
+        let mut rewards = HashSet::new();
+        for reward in &self.loyalty.rewards {
+            if !rewards.insert(reward.id) {
+                return Err(ConfigError::DuplicateReward(reward.id));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fixtures::{cart, config, order, CHASHU, EGG, NORI, RAMEN, SHOYU, TEA, TONKOTSU},
+        Allowance,
+    };
+
+    fn validate(order: Order, quantity: u32) -> Result<(), OrderError> {
+        cart(vec![(order, quantity)]).validate(&config().menu)
+    }
+
+    fn with_quantity(mut order: Order, id: OptionId, quantity: u32) -> Order {
+        order.options.push(ChosenOption { id, quantity });
+        order
+    }
+
+    #[test]
+    fn accepts_orders_within_the_limits() {
+        assert_eq!(validate(order(RAMEN, &[SHOYU, EGG]), 1), Ok(()));
+        assert_eq!(validate(order(TEA, &[]), CartItem::MAX_QUANTITY), Ok(()));
+        let triple = with_quantity(order(RAMEN, &[SHOYU]), CHASHU, 3);
+        assert_eq!(validate(triple, 1), Ok(()));
+    }
+
+    #[test]
+    fn rejects_bad_quantities() {
+        let menu = config().menu;
+        assert_eq!(cart(Vec::new()).validate(&menu), Err(OrderError::EmptyCart));
+        assert_eq!(validate(order(TEA, &[]), 0), Err(OrderError::ZeroQuantity));
+        assert_eq!(
+            validate(order(TEA, &[]), CartItem::MAX_QUANTITY + 1),
+            Err(OrderError::TooManyOfItem {
+                max: CartItem::MAX_QUANTITY
+            })
+        );
+        assert_eq!(
+            validate(order(TEA, &[]), u32::MAX),
+            Err(OrderError::TooManyOfItem {
+                max: CartItem::MAX_QUANTITY
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_options_past_their_limits() {
+        let none = with_quantity(order(RAMEN, &[SHOYU]), CHASHU, 0);
+        assert_eq!(
+            validate(none, 1),
+            Err(OrderError::OptionQuantity { id: CHASHU, max: 3 })
+        );
+        let four = with_quantity(order(RAMEN, &[SHOYU]), CHASHU, 4);
+        assert_eq!(
+            validate(four, 1),
+            Err(OrderError::OptionQuantity { id: CHASHU, max: 3 })
+        );
+        assert_eq!(
+            validate(order(RAMEN, &[SHOYU, EGG, EGG]), 1),
+            Err(OrderError::DuplicateOption(EGG))
+        );
+    }
+
+    #[test]
+    fn rejects_choices_off_the_menu() {
+        assert_eq!(
+            validate(order(99, &[]), 1),
+            Err(OrderError::UnknownItem(99))
+        );
+        assert_eq!(
+            validate(order(TEA, &[SHOYU]), 1),
+            Err(OrderError::UnknownOption(SHOYU))
+        );
+    }
+
+    #[test]
+    fn rejects_groups_outside_their_limits() {
+        assert_eq!(
+            validate(order(RAMEN, &[EGG]), 1),
+            Err(OrderError::TooFewOptions {
+                group: "Base".to_string(),
+                min: 1
+            })
+        );
+        assert_eq!(
+            validate(order(RAMEN, &[SHOYU, TONKOTSU]), 1),
+            Err(OrderError::TooManyOptions {
+                group: "Base".to_string(),
+                max: 1
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_sold_out_choices() {
+        let mut menu = config().menu;
+        menu.items_mut()
+            .flat_map(|item| item.modifier_groups.iter_mut())
+            .flat_map(|group| group.options.iter_mut())
+            .filter(|option| option.id == NORI)
+            .for_each(|option| option.sold_out = true);
+        let sold_out = cart(vec![(order(RAMEN, &[SHOYU, NORI]), 1)]);
+        assert_eq!(sold_out.validate(&menu), Err(OrderError::SoldOut(NORI)));
+    }
+
+    #[test]
+    fn reconcile_drops_what_is_no_longer_offered() {
+        let mut menu = config().menu;
+        let mut cart = cart(vec![
+            (order(TEA, &[]), 1),
+            (with_quantity(order(RAMEN, &[TONKOTSU, NORI]), CHASHU, 3), 2),
+        ]);
+
+        for item in menu.items_mut() {
+            item.sold_out = item.id == TEA;
+            for group in &mut item.modifier_groups {
+                for option in &mut group.options {
+                    option.sold_out = option.id == TONKOTSU;
+                    if option.id == CHASHU {
+                        option.max_quantity = 2;
+                    }
+                }
+            }
+        }
+        cart.reconcile(&menu);
+
+        assert_eq!(cart.items.len(), 1);
+        let ramen = &cart.items[0];
+        assert_eq!(ramen.quantity, 2);
+        // Tonkotsu sold out, so the base falls back to the default shoyu.
+        assert!(ramen.order.is_chosen(SHOYU));
+        assert!(!ramen.order.is_chosen(TONKOTSU));
+        assert_eq!(ramen.order.quantity(CHASHU), 2);
+        assert!(ramen.order.is_chosen(NORI));
+        assert_eq!(cart.validate(&menu), Ok(()));
+    }
+
+    #[test]
+    fn toggling_replaces_a_single_choice() {
+        let menu = config().menu;
+        let mut order = menu.new_order(RAMEN);
+        assert!(order.is_chosen(SHOYU) && order.is_chosen(EGG));
+
+        order.toggle(&menu, TONKOTSU);
+        assert!(order.is_chosen(TONKOTSU) && !order.is_chosen(SHOYU));
+        // The only base cannot be unpicked.
+        order.toggle(&menu, TONKOTSU);
+        assert!(order.is_chosen(TONKOTSU));
+
+        for _ in 0..5 {
+            order.add(&menu, CHASHU);
+        }
+        assert_eq!(order.quantity(CHASHU), 3);
+        order.remove(&menu, CHASHU);
+        assert_eq!(order.quantity(CHASHU), 2);
+    }
+
+    fn with_allowance(config: &Config, group: usize, option: OptionId) -> Config {
+        let mut config = config.clone();
+        let allowance = Allowance {
+            included: 0,
+            extra_price: Money::ZERO,
+        };
+        config.menu.categories[0].items[0].modifier_groups[group]
+            .allowances
+            .insert(option, allowance);
+        config
+    }
+
+    #[test]
+    fn keys_allowances_by_one_single_choice_group() {
+        let mut config = config();
+        // The toppings are already keyed by tonkotsu; shoyu is in the same
+        // group.
+        assert_eq!(with_allowance(&config, 1, SHOYU).validate(), Ok(()));
+
+        // Toppings can be chosen together, so they cannot key allowances.
+        assert_eq!(
+            with_allowance(&config, 0, EGG).validate(),
+            Err(ConfigError::AllowanceGroups("Base".to_string()))
+        );
+
+        // Nor can two groups at once, even single choice ones.
+        let mut noodles = config.menu.categories[0].items[0].modifier_groups[0].clone();
+        noodles.name = "Noodles".to_string();
+        noodles.options[0].id = 30;
+        noodles.options[1].id = 31;
+        config.menu.categories[0].items[0]
+            .modifier_groups
+            .push(noodles);
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(
+            with_allowance(&config, 1, 30).validate(),
+            Err(ConfigError::AllowanceGroups("Toppings".to_string()))
+        );
+    }
+
+    #[test]
+    fn checks_the_config() {
+        let config = config();
+        assert_eq!(config.validate(), Ok(()));
+
+        let mut allowance = config.clone();
+        let toppings = &mut allowance.menu.categories[0].items[0].modifier_groups[1];
+        toppings.allowances.insert(
+            CHASHU,
+            Allowance {
+                included: 0,
+                extra_price: Money::ZERO,
+            },
+        );
+        assert_eq!(
+            allowance.validate(),
+            Err(ConfigError::UnknownAllowanceOption {
+                group: "Toppings".to_string(),
+                option: CHASHU
+            })
+        );
+
+        let mut duplicate = config;
+        duplicate.menu.categories[1].items[0].id = RAMEN;
+        assert_eq!(duplicate.validate(), Err(ConfigError::DuplicateId(RAMEN)));
+    }
+}
@@ -0,0 +1,4 @@
+fn main() {
+    // Migrations are embedded by `sqlx::migrate!`, so rebuild when one is added.
+    println!("cargo:rerun-if-changed=migrations");
+}
@@ -0,0 +1,11 @@
+-- The schema operators used to create by hand, so existing databases pick up
+-- migration tracking without losing any data.
+CREATE TABLE IF NOT EXISTS orders (
+    time TIMESTAMP WITH TIME ZONE,
+    price MONEY
+);
+
+CREATE TABLE IF NOT EXISTS toppings (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL
+);
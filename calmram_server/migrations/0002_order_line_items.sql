@@ -0,0 +1,22 @@
+ALTER TABLE orders
+    ADD COLUMN IF NOT EXISTS id BIGSERIAL PRIMARY KEY,
+    ADD COLUMN IF NOT EXISTS order_number INTEGER,
+    ADD COLUMN IF NOT EXISTS spice_level INTEGER,
+    ADD COLUMN IF NOT EXISTS subtotal MONEY,
+    ADD COLUMN IF NOT EXISTS tax MONEY;
+
+-- Orders saved before line items were recorded only kept their total.
+UPDATE orders SET subtotal = price, tax = 0::numeric::money WHERE subtotal IS NULL;
+
+ALTER TABLE orders
+    ALTER COLUMN subtotal SET NOT NULL,
+    ALTER COLUMN tax SET NOT NULL;
+
+CREATE TABLE IF NOT EXISTS order_items (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    kind TEXT NOT NULL,
+    item_id INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    unit_price MONEY NOT NULL
+);
@@ -0,0 +1,15 @@
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS status TEXT;
+
+-- Orders from before statuses were tracked have long since been collected.
+UPDATE orders SET status = 'picked_up' WHERE status IS NULL;
+
+ALTER TABLE orders
+    ALTER COLUMN status SET NOT NULL,
+    ALTER COLUMN status SET DEFAULT 'received';
+
+CREATE TABLE IF NOT EXISTS order_status_changes (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    status TEXT NOT NULL,
+    time TIMESTAMP WITH TIME ZONE NOT NULL
+);
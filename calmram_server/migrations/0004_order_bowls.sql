@@ -0,0 +1,24 @@
+-- An order can hold several bowls, each made some number of times with its
+-- own spice level. Line items belong to a bowl, and orders.spice_level is no
+-- longer written.
+CREATE TABLE IF NOT EXISTS order_bowls (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    position INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    spice_level INTEGER NOT NULL,
+    unit_price MONEY NOT NULL
+);
+
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS bowl_id BIGINT REFERENCES order_bowls (id);
+
+-- Every order with line items so far was a single bowl.
+INSERT INTO order_bowls (order_id, position, quantity, spice_level, unit_price)
+SELECT id, 0, 1, spice_level, subtotal FROM orders
+WHERE id IN (SELECT order_id FROM order_items WHERE bowl_id IS NULL);
+
+UPDATE order_items SET bowl_id = order_bowls.id
+FROM order_bowls
+WHERE order_bowls.order_id = order_items.order_id AND order_items.bowl_id IS NULL;
+
+ALTER TABLE order_items ALTER COLUMN bowl_id SET NOT NULL;
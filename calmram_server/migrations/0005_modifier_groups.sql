@@ -0,0 +1,10 @@
+-- Bowls are now a menu item plus the options chosen from its modifier
+-- groups. The spice level is one of those options, so order_bowls.spice_level
+-- is no longer written.
+ALTER TABLE order_bowls ALTER COLUMN spice_level DROP NOT NULL;
+
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS group_name TEXT;
+
+-- Bases were priced as the bowl itself, and toppings were the only options.
+UPDATE order_items SET kind = 'item' WHERE kind = 'base';
+UPDATE order_items SET kind = 'modifier', group_name = 'Toppings' WHERE kind = 'topping';
@@ -0,0 +1,4 @@
+-- Orders hold sides and drinks as well as bowls, so the rows grouping an
+-- order's line items are named after the cart items they came from.
+ALTER TABLE order_bowls RENAME TO order_cart_items;
+ALTER TABLE order_items RENAME COLUMN bowl_id TO cart_item_id;
@@ -0,0 +1,3 @@
+-- An option can be ordered more than once on the same item ("extra chashu").
+-- unit_price stays the price of a single helping.
+ALTER TABLE order_items ADD COLUMN quantity INTEGER NOT NULL DEFAULT 1;
@@ -0,0 +1,11 @@
+-- Each tax charged on an order, as shown on the kiosk's summary. The order's
+-- tax column stays the sum of them.
+ALTER TABLE orders ADD COLUMN tax_inclusive BOOLEAN NOT NULL DEFAULT FALSE;
+
+CREATE TABLE order_taxes (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    name TEXT NOT NULL,
+    percent NUMERIC(6, 3) NOT NULL,
+    amount MONEY NOT NULL
+);
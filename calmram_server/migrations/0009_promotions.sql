@@ -0,0 +1,22 @@
+-- Promo codes, entered on the kiosk's order page. Which of percent, amount,
+-- item_id and option_id is set depends on the kind of discount.
+CREATE TABLE promotions (
+    id SERIAL PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE CHECK (code = upper(code)),
+    name TEXT NOT NULL,
+    kind TEXT NOT NULL
+        CHECK (kind IN ('percent_off', 'amount_off', 'buy_one_get_one', 'free_option')),
+    percent NUMERIC(6, 3),
+    amount MONEY,
+    item_id INTEGER,
+    option_id INTEGER,
+    starts_at TIMESTAMP WITH TIME ZONE,
+    ends_at TIMESTAMP WITH TIME ZONE,
+    -- No limit when NULL.
+    max_uses INTEGER,
+    uses INTEGER NOT NULL DEFAULT 0
+);
+
+ALTER TABLE orders
+    ADD COLUMN promotion_id INTEGER REFERENCES promotions (id),
+    ADD COLUMN discount MONEY NOT NULL DEFAULT 0;
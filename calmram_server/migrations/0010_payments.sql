@@ -0,0 +1,10 @@
+-- Card payments taken for orders. Orders are only saved once their payment
+-- has gone through.
+CREATE TABLE payments (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    provider TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    amount MONEY NOT NULL,
+    time TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,10 @@
+-- Voids and refunds of paid orders. Orders and payments are never changed
+-- after the sale; what was given back is recorded here instead.
+CREATE TABLE order_adjustments (
+    id BIGSERIAL PRIMARY KEY,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    kind TEXT NOT NULL CHECK (kind IN ('void', 'refund')),
+    amount MONEY NOT NULL,
+    reason TEXT NOT NULL,
+    time TIMESTAMP WITH TIME ZONE NOT NULL
+);
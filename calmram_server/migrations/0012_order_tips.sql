@@ -0,0 +1,3 @@
+-- Tips are kept apart from what the order itself cost. `price` is what the
+-- customer paid, tip included.
+ALTER TABLE orders ADD COLUMN tip MONEY NOT NULL DEFAULT 0;
@@ -0,0 +1,15 @@
+-- Orders can be split between several tenders, such as two cards or part
+-- card and part cash at the counter. Card payments become card tenders.
+ALTER TABLE payments RENAME TO tenders;
+ALTER TABLE tenders
+    ADD COLUMN kind TEXT NOT NULL DEFAULT 'card' CHECK (kind IN ('card', 'cash')),
+    -- Only card tenders go through a provider.
+    ALTER COLUMN provider DROP NOT NULL,
+    ALTER COLUMN reference DROP NOT NULL;
+ALTER TABLE tenders ALTER COLUMN kind DROP DEFAULT;
+
+-- Each void or refund gives money back to one tender of the order.
+ALTER TABLE order_adjustments ADD COLUMN tender_id BIGINT REFERENCES tenders (id);
+UPDATE order_adjustments
+SET tender_id = (SELECT id FROM tenders WHERE tenders.order_id = order_adjustments.order_id);
+ALTER TABLE order_adjustments ALTER COLUMN tender_id SET NOT NULL;
@@ -0,0 +1,22 @@
+-- Gift cards customers can pay with. A card's balance is the sum of its
+-- entries, each of which is a credit (positive) or debit (negative).
+CREATE TABLE gift_cards (
+    id SERIAL PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE CHECK (code = upper(code)),
+    issued_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+ALTER TABLE tenders DROP CONSTRAINT tenders_kind_check;
+ALTER TABLE tenders
+    ADD CONSTRAINT tenders_kind_check CHECK (kind IN ('card', 'cash', 'gift_card')),
+    ADD COLUMN gift_card_id INTEGER REFERENCES gift_cards (id);
+
+CREATE TABLE gift_card_entries (
+    id BIGSERIAL PRIMARY KEY,
+    gift_card_id INTEGER NOT NULL REFERENCES gift_cards (id),
+    kind TEXT NOT NULL CHECK (kind IN ('issue', 'redeem', 'refund')),
+    amount MONEY NOT NULL,
+    -- The tender of the order the card paid for or was refunded to.
+    tender_id BIGINT REFERENCES tenders (id),
+    time TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,22 @@
+-- Loyalty members, known by the phone number they give at the kiosk. Like
+-- gift cards, a customer's points are the sum of their entries, each of which
+-- earns (positive) or spends (negative) points.
+CREATE TABLE customers (
+    id SERIAL PRIMARY KEY,
+    -- Digits only.
+    phone TEXT NOT NULL UNIQUE,
+    joined_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+ALTER TABLE orders ADD COLUMN customer_id INTEGER REFERENCES customers (id);
+
+CREATE TABLE loyalty_entries (
+    id BIGSERIAL PRIMARY KEY,
+    customer_id INTEGER NOT NULL REFERENCES customers (id),
+    kind TEXT NOT NULL CHECK (kind IN ('earn', 'redeem', 'void')),
+    points INTEGER NOT NULL,
+    order_id BIGINT NOT NULL REFERENCES orders (id),
+    -- The reward from the menu config that a `redeem` entry spent points on.
+    reward_id INTEGER,
+    time TIMESTAMP WITH TIME ZONE NOT NULL
+);
@@ -0,0 +1,20 @@
+-- Order numbers are handed out by the database, starting again at 1 each
+-- business day, so they carry on where they left off when the server
+-- restarts. `last_number` is the last one handed out on `day`.
+CREATE TABLE order_number_days (
+    day DATE PRIMARY KEY,
+    last_number INTEGER NOT NULL
+);
+
+-- Orders from before this have no business day, since a restart may have
+-- given two of them the same number. Numbering carries on after the highest
+-- number they reached each day.
+INSERT INTO order_number_days (day, last_number)
+SELECT time::DATE, MAX(order_number)
+FROM orders
+WHERE time IS NOT NULL AND order_number IS NOT NULL
+GROUP BY time::DATE;
+
+ALTER TABLE orders
+    ADD COLUMN business_day DATE,
+    ADD CONSTRAINT orders_business_day_order_number_key UNIQUE (business_day, order_number);
@@ -0,0 +1,11 @@
+-- Card refunds are saved as pending before the provider is asked for the
+-- money, and marked complete once it has been given back, so one that fails
+-- can be retried without refunding twice.
+ALTER TABLE order_adjustments
+    ADD COLUMN state TEXT NOT NULL DEFAULT 'complete' CHECK (state IN ('pending', 'complete'));
+ALTER TABLE order_adjustments ALTER COLUMN state DROP DEFAULT;
+
+-- Refunds take back the part of the points an order earned that was refunded.
+ALTER TABLE loyalty_entries DROP CONSTRAINT loyalty_entries_kind_check;
+ALTER TABLE loyalty_entries ADD CONSTRAINT loyalty_entries_kind_check
+    CHECK (kind IN ('earn', 'redeem', 'refund', 'void'));
@@ -0,0 +1,5 @@
+-- A percent off is never more than the whole order, and nothing takes money
+-- off below zero.
+ALTER TABLE promotions
+    ADD CONSTRAINT promotions_percent_check CHECK (percent BETWEEN 0 AND 100),
+    ADD CONSTRAINT promotions_amount_check CHECK (amount >= 0::MONEY);
@@ -0,0 +1,9 @@
+-- Earning adds points and spending or refunding takes them away, so each
+-- entry's sign is fixed by its kind. Rewards can be free, and voids undo
+-- entries of either sign.
+ALTER TABLE loyalty_entries ADD CONSTRAINT loyalty_entries_points_check CHECK (
+    (kind = 'earn' AND points > 0)
+    OR (kind = 'redeem' AND points <= 0)
+    OR (kind = 'refund' AND points < 0)
+    OR kind = 'void'
+);
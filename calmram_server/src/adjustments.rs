@@ -0,0 +1,602 @@
+//! Voids and refunds of orders that were already paid for and saved.
+//!
+//! Adjustments are recorded as rows of their own in `order_adjustments`; the
+//! order and its tenders are never changed. Every route here needs the
+//! manager PIN set as `manager_pin` in Rocket.toml, sent in a `Manager-Pin`
+//! header.
+
+use std::sync::Arc;
+
+use calmram_lib::{protocol::ServerMessage, Money, OrderStatus};
+use rocket::{
+    http::Status,
+    request::{FromRequest, Outcome, Request},
+    serde::json::Json,
+    State,
+};
+use rocket_db_pools::Connection;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::types::PgMoney, types::time::OffsetDateTime, Connection as _, PgConnection};
+use subtle::ConstantTimeEq;
+
+use crate::{
+    config::MenuConfig,
+    gift_cards::{self, EntryKind},
+    kitchen::Kitchen,
+    loyalty, orders,
+    payments::{Authorization, PaymentProvider},
+    promotions,
+    registry::Registry,
+    Db,
+};
+
+/// The PIN managers enter to adjust orders. Adjustments are refused when
+/// none is configured.
+pub struct ManagerPin(pub Option<String>);
+
+/// A request made with the manager PIN.
+pub struct Manager;
+
+/// Compares PINs in constant time, so how long it takes to refuse a guess
+/// says nothing about how close it was.
+fn pin_matches(expected: &str, given: &str) -> bool {
+    expected.as_bytes().ct_eq(given.as_bytes()).into()
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Manager {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> Outcome<Self, ()> {
+        let expected = request
+            .rocket()
+            .state::<ManagerPin>()
+            .and_then(|pin| pin.0.as_deref());
+        match (expected, request.headers().get_one("Manager-Pin")) {
+            (Some(expected), Some(given)) if pin_matches(expected, given) => {
+                Outcome::Success(Manager)
+            }
+            _ => Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentKind {
+    /// Cancels the whole sale, refunding whatever is left of it and putting
+    /// its items back in stock.
+    Void,
+    /// Gives back part of what was paid.
+    Refund,
+}
+
+impl AdjustmentKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            AdjustmentKind::Void => "void",
+            AdjustmentKind::Refund => "refund",
+        }
+    }
+}
+
+/// Whether the money of an adjustment has been given back. Card refunds are
+/// saved as pending before the provider is asked for them, so one that fails
+/// is neither lost nor sent twice, and can be retried.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AdjustmentState {
+    Pending,
+    Complete,
+}
+
+impl AdjustmentState {
+    fn as_str(self) -> &'static str {
+        match self {
+            AdjustmentState::Pending => "pending",
+            AdjustmentState::Complete => "complete",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct Adjustment {
+    pub id: i64,
+    pub order_id: i64,
+    /// The tender the money went back to.
+    pub tender_id: i64,
+    pub kind: AdjustmentKind,
+    pub amount: Money,
+    pub reason: String,
+    pub state: AdjustmentState,
+}
+
+#[derive(Deserialize)]
+pub struct VoidRequest {
+    reason: String,
+}
+
+#[derive(Deserialize)]
+pub struct RefundRequest {
+    amount: Money,
+    reason: String,
+}
+
+type AdjustmentResult = Result<Json<Vec<Adjustment>>, (Status, String)>;
+
+fn db_error(err: sqlx::Error) -> (Status, String) {
+    println!("Could not adjust order: {}", err);
+    (
+        Status::InternalServerError,
+        "the database failed".to_string(),
+    )
+}
+
+/// One tender of a paid order and how much of it has been given back.
+struct PaidTender {
+    id: i64,
+    /// The card payment, for card tenders.
+    card: Option<Authorization>,
+    /// The gift card paid from, for gift card tenders.
+    gift_card_id: Option<i32>,
+    amount: Money,
+    refunded: Money,
+}
+
+impl PaidTender {
+    fn refundable(&self) -> Money {
+        self.amount - self.refunded
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct TenderRow {
+    id: i64,
+    kind: String,
+    reference: Option<String>,
+    gift_card_id: Option<i32>,
+    amount: PgMoney,
+    refunded: Option<PgMoney>,
+}
+
+/// What was paid for an order and how much of it has been given back.
+struct Paid {
+    status: OrderStatus,
+    tenders: Vec<PaidTender>,
+    voided: bool,
+}
+
+/// Looks up what was paid for `order_id`, locking the order until the end of
+/// the transaction `db` is in so adjustments to it happen one at a time.
+async fn paid(db: &mut PgConnection, order_id: i64) -> Result<Paid, (Status, String)> {
+    sqlx::query("SELECT 1 FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .execute(&mut *db)
+        .await
+        .map_err(db_error)?;
+
+    let order: Option<(String, bool)> = sqlx::query_as(
+        "SELECT status,
+                EXISTS (SELECT 1 FROM order_adjustments
+                        WHERE order_id = orders.id AND kind = 'void')
+         FROM orders
+         WHERE id = $1",
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *db)
+    .await
+    .map_err(db_error)?;
+    let not_found = || {
+        (
+            Status::NotFound,
+            format!("there is no paid order {order_id}"),
+        )
+    };
+    let (status, voided) = order.ok_or_else(not_found)?;
+
+    let tenders: Vec<TenderRow> = sqlx::query_as(
+        "SELECT id, kind, reference, gift_card_id, amount,
+                (SELECT SUM(amount) FROM order_adjustments WHERE tender_id = tenders.id)
+                    AS refunded
+         FROM tenders
+         WHERE order_id = $1
+         ORDER BY id",
+    )
+    .bind(order_id)
+    .fetch_all(db)
+    .await
+    .map_err(db_error)?;
+    if tenders.is_empty() {
+        return Err(not_found());
+    }
+
+    Ok(Paid {
+        status: status
+            .parse()
+            .map_err(|err| (Status::InternalServerError, err))?,
+        tenders: tenders
+            .into_iter()
+            .map(|row| {
+                let amount = Money::from_cents(row.amount.0);
+                let is_card = row.kind == "card";
+                PaidTender {
+                    id: row.id,
+                    card: row
+                        .reference
+                        .filter(|_| is_card)
+                        .map(|reference| Authorization { reference, amount }),
+                    gift_card_id: row.gift_card_id,
+                    amount,
+                    refunded: Money::from_cents(row.refunded.map_or(0, |refunded| refunded.0)),
+                }
+            })
+            .collect(),
+        voided,
+    })
+}
+
+/// Records giving `amount` of `tender` back. Gift cards have it put back on
+/// them straight away and cash is handed back at the counter, while card
+/// adjustments are left pending until [`finish`] has the provider refund
+/// them, which happens once the transaction `db` is in has been committed.
+async fn record(
+    db: &mut PgConnection,
+    order_id: i64,
+    tender: &PaidTender,
+    kind: AdjustmentKind,
+    amount: Money,
+    reason: &str,
+) -> Result<Adjustment, (Status, String)> {
+    if let Some(gift_card_id) = tender.gift_card_id.filter(|_| !amount.is_zero()) {
+        gift_cards::record(
+            &mut *db,
+            gift_card_id,
+            EntryKind::Refund,
+            amount,
+            Some(tender.id),
+        )
+        .await
+        .map_err(db_error)?;
+    }
+
+    let state = if tender.card.is_some() && !amount.is_zero() {
+        AdjustmentState::Pending
+    } else {
+        AdjustmentState::Complete
+    };
+    let (id,): (i64,) = sqlx::query_as(
+        "INSERT INTO order_adjustments (order_id, tender_id, kind, amount, reason, state, time)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING id",
+    )
+    .bind(order_id)
+    .bind(tender.id)
+    .bind(kind.as_str())
+    .bind(PgMoney(amount.cents()))
+    .bind(reason)
+    .bind(state.as_str())
+    .bind(OffsetDateTime::now_utc())
+    .fetch_one(db)
+    .await
+    .map_err(db_error)?;
+
+    println!(
+        "{} of {} on order {}: {}",
+        kind.as_str(),
+        amount,
+        order_id,
+        reason
+    );
+    Ok(Adjustment {
+        id,
+        order_id,
+        tender_id: tender.id,
+        kind,
+        amount,
+        reason: reason.to_string(),
+        state,
+    })
+}
+
+#[derive(sqlx::FromRow)]
+struct AdjustmentRow {
+    order_id: i64,
+    tender_id: i64,
+    kind: String,
+    amount: PgMoney,
+    reason: String,
+    state: String,
+    reference: Option<String>,
+    paid: PgMoney,
+}
+
+/// Has the provider refund adjustment `id` if it is still pending and marks
+/// it complete. The adjustment stays locked while the provider is asked, so
+/// the same money is never given back twice, and stays pending if the
+/// provider fails so it can be retried.
+async fn finish(
+    db: &mut PgConnection,
+    payments: &dyn PaymentProvider,
+    id: i64,
+) -> Result<Adjustment, (Status, String)> {
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let row: AdjustmentRow = sqlx::query_as(
+        "SELECT order_adjustments.order_id, tender_id, order_adjustments.kind,
+                order_adjustments.amount, reason, state, tenders.reference,
+                tenders.amount AS paid
+         FROM order_adjustments
+         JOIN tenders ON tenders.id = order_adjustments.tender_id
+         WHERE order_adjustments.id = $1
+         FOR UPDATE OF order_adjustments",
+    )
+    .bind(id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?
+    .ok_or_else(|| (Status::NotFound, format!("there is no adjustment {id}")))?;
+
+    let kind = match row.kind.as_str() {
+        "void" => AdjustmentKind::Void,
+        _ => AdjustmentKind::Refund,
+    };
+    let amount = Money::from_cents(row.amount.0);
+    let adjustment = Adjustment {
+        id,
+        order_id: row.order_id,
+        tender_id: row.tender_id,
+        kind,
+        amount,
+        reason: row.reason,
+        state: AdjustmentState::Complete,
+    };
+    if row.state == AdjustmentState::Complete.as_str() {
+        return Ok(adjustment);
+    }
+
+    if let Some(reference) = row.reference {
+        let card = Authorization {
+            reference,
+            amount: Money::from_cents(row.paid.0),
+        };
+        if let Err(err) = payments.refund(&card, amount).await {
+            println!("Could not refund adjustment {id}: {err}");
+            return Err((
+                Status::BadGateway,
+                format!(
+                    "{} of order {} was saved but the card was not refunded ({err}), \
+                     retry it at /adjustments/{id}/retry",
+                    kind.as_str(),
+                    row.order_id
+                ),
+            ));
+        }
+    }
+
+    sqlx::query("UPDATE order_adjustments SET state = $2 WHERE id = $1")
+        .bind(id)
+        .bind(AdjustmentState::Complete.as_str())
+        .execute(&mut *tx)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+    Ok(adjustment)
+}
+
+/// Finishes every pending one of `adjustments`, which have been committed.
+async fn finish_all(
+    db: &mut PgConnection,
+    payments: &dyn PaymentProvider,
+    adjustments: Vec<Adjustment>,
+) -> AdjustmentResult {
+    let mut finished = Vec::new();
+    for adjustment in adjustments {
+        finished.push(match adjustment.state {
+            AdjustmentState::Pending => finish(&mut *db, payments, adjustment.id).await?,
+            AdjustmentState::Complete => adjustment,
+        });
+    }
+    Ok(Json(finished))
+}
+
+/// Whether voiding an order in `status` puts its items back in stock, which
+/// they are not once it has been picked up.
+fn returns_stock(status: OrderStatus) -> bool {
+    status.can_become(OrderStatus::Cancelled)
+}
+
+/// Splits `amount` between `tenders`, starting with the last one, giving
+/// back no more than is left of each.
+fn spread(tenders: &[PaidTender], amount: Money) -> Vec<(&PaidTender, Money)> {
+    let mut parts = Vec::new();
+    let mut remaining = amount;
+    for tender in tenders.iter().rev() {
+        let part = tender.refundable().min(remaining);
+        if part <= Money::ZERO {
+            continue;
+        }
+        parts.push((tender, part));
+        remaining -= part;
+    }
+    parts
+}
+
+/// Voids an order, giving back whatever is left of each of its tenders, the
+/// use of its promo code and the points it earned or spent.
+#[allow(clippy::too_many_arguments)]
+#[post("/orders/<order_id>/void", data = "<request>")]
+pub async fn void(
+    _manager: Manager,
+    order_id: i64,
+    request: Json<VoidRequest>,
+    menu_config: &State<Arc<MenuConfig>>,
+    kiosks: &State<Arc<Registry<ServerMessage>>>,
+    kitchen: &State<Kitchen>,
+    payments: &State<Arc<dyn PaymentProvider>>,
+    mut db: Connection<Db>,
+) -> AdjustmentResult {
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let paid = paid(&mut tx, order_id).await?;
+    if paid.voided {
+        return Err((
+            Status::Conflict,
+            format!("order {order_id} is already void"),
+        ));
+    }
+
+    let mut adjustments = Vec::new();
+    for tender in &paid.tenders {
+        adjustments.push(
+            record(
+                &mut tx,
+                order_id,
+                tender,
+                AdjustmentKind::Void,
+                tender.refundable(),
+                &request.reason,
+            )
+            .await?,
+        );
+    }
+    promotions::release(&mut tx, order_id)
+        .await
+        .map_err(db_error)?;
+    loyalty::void(&mut tx, order_id).await.map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    // Take the order off the kitchen screens if it is still being made, and
+    // put back what it would have used.
+    if returns_stock(paid.status) {
+        kitchen
+            .set_status(kiosks, &mut db, order_id, OrderStatus::Cancelled)
+            .await;
+
+        let used = orders::stock_used(&mut db, order_id)
+            .await
+            .map_err(db_error)?;
+        if menu_config.inventory().return_stock(&used) {
+            kiosks.broadcast(&ServerMessage::MenuUpdated(menu_config.current()));
+        }
+    }
+
+    finish_all(&mut db, &***payments, adjustments).await
+}
+
+/// Gives back part of what was paid for an order, starting with the last
+/// tender it was paid with, along with the same part of the points it
+/// earned.
+#[post("/orders/<order_id>/refund", data = "<request>")]
+pub async fn refund(
+    _manager: Manager,
+    order_id: i64,
+    request: Json<RefundRequest>,
+    payments: &State<Arc<dyn PaymentProvider>>,
+    mut db: Connection<Db>,
+) -> AdjustmentResult {
+    let RefundRequest { amount, reason } = request.into_inner();
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let paid = paid(&mut tx, order_id).await?;
+    if paid.voided {
+        return Err((Status::Conflict, format!("order {order_id} is void")));
+    }
+
+    let refundable: Money = paid.tenders.iter().map(PaidTender::refundable).sum();
+    if amount <= Money::ZERO || amount > refundable {
+        return Err((
+            Status::UnprocessableEntity,
+            format!(
+                "a refund of order {order_id} must be more than $0.00 and at most {refundable}"
+            ),
+        ));
+    }
+
+    let mut adjustments = Vec::new();
+    for (tender, part) in spread(&paid.tenders, amount) {
+        adjustments.push(
+            record(
+                &mut tx,
+                order_id,
+                tender,
+                AdjustmentKind::Refund,
+                part,
+                &reason,
+            )
+            .await?,
+        );
+    }
+
+    let total: Money = paid.tenders.iter().map(|tender| tender.amount).sum();
+    let refunded: Money = paid.tenders.iter().map(|tender| tender.refunded).sum();
+    loyalty::refund(&mut tx, order_id, refunded + amount, total)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    finish_all(&mut db, &***payments, adjustments).await
+}
+
+/// Tries again to give back the money of an adjustment whose card refund
+/// failed.
+#[post("/adjustments/<id>/retry")]
+pub async fn retry(
+    _manager: Manager,
+    id: i64,
+    payments: &State<Arc<dyn PaymentProvider>>,
+    mut db: Connection<Db>,
+) -> Result<Json<Adjustment>, (Status, String)> {
+    finish(&mut db, &***payments, id).await.map(Json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tender(id: i64, amount: i64, refunded: i64) -> PaidTender {
+        PaidTender {
+            id,
+            card: None,
+            gift_card_id: None,
+            amount: Money::from_cents(amount),
+            refunded: Money::from_cents(refunded),
+        }
+    }
+
+    fn parts(tenders: &[PaidTender], amount: i64) -> Vec<(i64, i64)> {
+        spread(tenders, Money::from_cents(amount))
+            .into_iter()
+            .map(|(tender, part)| (tender.id, part.cents()))
+            .collect()
+    }
+
+    #[test]
+    fn pins_must_match_exactly() {
+        assert!(pin_matches("1234", "1234"));
+        assert!(!pin_matches("1234", "1235"));
+        assert!(!pin_matches("1234", "123"));
+        assert!(!pin_matches("1234", "12345"));
+        assert!(!pin_matches("1234", ""));
+    }
+
+    #[test]
+    fn refunds_start_with_the_last_tender() {
+        let tenders = [tender(1, 1000, 0), tender(2, 500, 0)];
+        assert_eq!(parts(&tenders, 300), [(2, 300)]);
+        assert_eq!(parts(&tenders, 800), [(2, 500), (1, 300)]);
+        assert_eq!(parts(&tenders, 1500), [(2, 500), (1, 1000)]);
+    }
+
+    #[test]
+    fn refunds_skip_what_was_already_given_back() {
+        let tenders = [tender(1, 1000, 200), tender(2, 500, 500)];
+        assert_eq!(parts(&tenders, 300), [(1, 300)]);
+        assert_eq!(parts(&tenders, 800), [(1, 800)]);
+    }
+
+    #[test]
+    fn only_open_orders_are_restocked() {
+        assert!(returns_stock(OrderStatus::Received));
+        assert!(returns_stock(OrderStatus::Preparing));
+        assert!(returns_stock(OrderStatus::Ready));
+        assert!(!returns_stock(OrderStatus::PickedUp));
+        assert!(!returns_stock(OrderStatus::Cancelled));
+    }
+}
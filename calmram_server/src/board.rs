@@ -0,0 +1,54 @@
+use calmram_lib::protocol::{self, BoardServerMessage};
+use rocket::{
+    futures::{SinkExt, StreamExt},
+    tokio::select,
+    State,
+};
+use rocket_db_pools::Connection;
+use ws::Message;
+
+use crate::{kitchen::Kitchen, orders, Db};
+
+fn reply(message: &BoardServerMessage) -> Message {
+    Message::text(protocol::encode(message))
+}
+
+/// The pickup board's feed: every open order on connect, then each status
+/// change as the kitchen makes it.
+#[get("/board")]
+pub fn board<'r>(
+    ws: ws::WebSocket,
+    kitchen: &'r State<Kitchen>,
+    mut db: Connection<Db>,
+) -> ws::Channel<'r> {
+    ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut board = kitchen.boards().register();
+            info!("Pickup board {} connected", board.id);
+
+            match orders::open_orders(&mut db).await {
+                Ok(open_orders) => {
+                    stream
+                        .send(reply(&BoardServerMessage::Orders(open_orders)))
+                        .await?
+                }
+                Err(err) => println!("Could not load open orders: {}", err),
+            }
+
+            loop {
+                select! {
+                    message = stream.next() => {
+                        let Some(message) = message else {
+                            break;
+                        };
+                        println!("Received unexpected message: {:?}", message?);
+                    }
+                    Some(message) = board.receiver.recv() => {
+                        stream.send(reply(&message)).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    })
+}
@@ -0,0 +1,107 @@
+use calmram_lib::{LocalTime, TimeOfDay, Weekday};
+use sqlx::types::time::{Date, OffsetDateTime, PrimitiveDateTime, UtcOffset};
+
+/// The server's clock in the restaurant's time zone, which menu schedules
+/// and business days are in. The zone is a fixed offset from UTC, set as
+/// `utc_offset` in Rocket.toml, such as "-05:00".
+#[derive(Clone, Copy, Debug)]
+pub struct Clock {
+    offset: UtcOffset,
+}
+
+impl Clock {
+    pub fn new(offset: UtcOffset) -> Self {
+        Clock { offset }
+    }
+
+    /// Reads an offset like "+09:00", "-05:30" or "Z".
+    pub fn parse(offset: &str) -> Result<Clock, String> {
+        let invalid = || format!("{offset:?} is not a UTC offset like \"-05:00\"");
+        if offset == "Z" {
+            return Ok(Clock::new(UtcOffset::UTC));
+        }
+
+        let (sign, rest) = match offset.chars().next() {
+            Some('+') => (1, &offset[1..]),
+            Some('-') => (-1, &offset[1..]),
+            _ => return Err(invalid()),
+        };
+        let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+        let hours: i8 = hours.parse().map_err(|_| invalid())?;
+        let minutes: i8 = minutes.parse().map_err(|_| invalid())?;
+        UtcOffset::from_hms(sign * hours, sign * minutes, 0)
+            .map(Clock::new)
+            .map_err(|_| invalid())
+    }
+
+    /// How far the restaurant's clock is ahead of UTC.
+    pub fn offset(&self) -> UtcOffset {
+        self.offset
+    }
+
+    /// The moment the restaurant's clock reads `time`.
+    pub fn at(&self, time: PrimitiveDateTime) -> OffsetDateTime {
+        time.assume_offset(self.offset)
+    }
+
+    fn local(&self) -> OffsetDateTime {
+        OffsetDateTime::now_utc().to_offset(self.offset)
+    }
+
+    /// The time of the week now, as menu schedules are written.
+    pub fn now(&self) -> LocalTime {
+        let local = self.local();
+        LocalTime {
+            weekday: Weekday::from_monday(u32::from(local.weekday().number_days_from_monday())),
+            time: TimeOfDay::new(local.hour(), local.minute())
+                .expect("the clock gives a valid time of day"),
+        }
+    }
+
+    /// The business day orders placed now belong to.
+    pub fn today(&self) -> Date {
+        self.local().date()
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new(UtcOffset::UTC)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rocket::time::{Month, Time};
+
+    use super::*;
+
+    fn offset(clock: Clock) -> (i8, i8) {
+        let (hours, minutes, _) = clock.offset.as_hms();
+        (hours, minutes)
+    }
+
+    #[test]
+    fn reads_utc_offsets() {
+        assert_eq!(offset(Clock::parse("Z").unwrap()), (0, 0));
+        assert_eq!(offset(Clock::parse("+09:00").unwrap()), (9, 0));
+        assert_eq!(offset(Clock::parse("-05:30").unwrap()), (-5, -30));
+        assert_eq!(offset(Clock::parse("+5").unwrap()), (5, 0));
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        for invalid in ["", "05:00", "+", "-5:xx", "+26:00", "+05:60", "EST"] {
+            assert!(Clock::parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn reads_times_on_the_restaurants_clock() {
+        let clock = Clock::parse("-05:00").unwrap();
+        let date = Date::from_calendar_date(2024, Month::June, 1).unwrap();
+        let time = clock.at(date.with_time(Time::from_hms(17, 0, 0).unwrap()));
+        let utc = time.to_offset(UtcOffset::UTC);
+        assert_eq!((utc.date(), utc.hour()), (date, 22));
+    }
+}
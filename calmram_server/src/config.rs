@@ -0,0 +1,134 @@
+use std::{
+    error::Error,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use calmram_lib::{protocol::ServerMessage, Config, ConfigError};
+use rocket::tokio::time;
+
+use crate::{clock::Clock, inventory::Inventory, registry::Registry};
+
+/// How often the config file is checked for changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum LoadError {
+    Io(io::Error),
+    Parse(serde_json::Error),
+    Invalid(ConfigError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(err) => write!(f, "could not read file: {err}"),
+            LoadError::Parse(err) => write!(f, "could not parse file: {err}"),
+            LoadError::Invalid(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+fn load(path: &Path) -> Result<Config, LoadError> {
+    let text = fs::read_to_string(path).map_err(LoadError::Io)?;
+    let config: Config = serde_json::from_str(&text).map_err(LoadError::Parse)?;
+    config.validate().map_err(LoadError::Invalid)?;
+    Ok(config)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// The [`Config`] currently being served, kept in sync with the file it was
+/// loaded from and with the stock left of each item.
+pub struct MenuConfig {
+    path: PathBuf,
+    loaded: RwLock<Arc<Config>>,
+    inventory: Inventory,
+    clock: Clock,
+}
+
+impl MenuConfig {
+    pub fn load(path: PathBuf, clock: Clock) -> Result<MenuConfig, LoadError> {
+        let config = load(&path)?;
+        Ok(MenuConfig {
+            path,
+            clock,
+            inventory: Inventory::new(&config.menu),
+            loaded: RwLock::new(Arc::new(config)),
+        })
+    }
+
+    /// The config as kiosks should see it: the menu offered at this time of
+    /// day, with items that ran out of stock marked sold out.
+    pub fn current(&self) -> Config {
+        let mut config = self.scheduled();
+        self.inventory.apply(&mut config.menu);
+        config
+    }
+
+    fn scheduled(&self) -> Config {
+        self.loaded.read().unwrap().at(self.clock.now())
+    }
+
+    pub fn inventory(&self) -> &Inventory {
+        &self.inventory
+    }
+
+    /// The clock the menu's schedules are kept by.
+    pub fn clock(&self) -> Clock {
+        self.clock
+    }
+
+    /// Reloads the config whenever its file changes and sends the new menu to
+    /// every connected kiosk, as well as whenever a schedule in it starts or
+    /// ends. A file that fails to load is reported and the current config is
+    /// kept.
+    pub async fn watch(self: Arc<Self>, kiosks: Arc<Registry<ServerMessage>>) {
+        let mut last_modified = modified(&self.path);
+        let mut scheduled = self.scheduled();
+        let mut interval = time::interval(POLL_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            let modified = modified(&self.path);
+            if modified == last_modified {
+                let now_scheduled = self.scheduled();
+                if now_scheduled != scheduled {
+                    info!(
+                        "The menu schedule changed, updating {} kiosks",
+                        kiosks.len()
+                    );
+                    scheduled = now_scheduled;
+                    kiosks.broadcast(&ServerMessage::MenuUpdated(self.current()));
+                }
+                continue;
+            }
+            last_modified = modified;
+
+            match load(&self.path) {
+                Ok(config) => {
+                    info!(
+                        "Reloaded menu config from {}, updating {} kiosks",
+                        self.path.display(),
+                        kiosks.len()
+                    );
+                    self.inventory.restock(&config.menu);
+                    *self.loaded.write().unwrap() = Arc::new(config);
+                    scheduled = self.scheduled();
+                    kiosks.broadcast(&ServerMessage::MenuUpdated(self.current()));
+                }
+                Err(err) => error!(
+                    "Keeping the current menu config, {} is invalid: {}",
+                    self.path.display(),
+                    err
+                ),
+            }
+        }
+    }
+}
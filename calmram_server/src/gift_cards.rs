@@ -0,0 +1,225 @@
+//! Gift cards, which hold a balance customers can pay with at the kiosk.
+//!
+//! A card's balance is never stored. Every credit and debit is a row of
+//! `gift_card_entries`, and the balance is what they add up to, so it can
+//! always be traced back to how it came about.
+
+use std::{error::Error, fmt};
+
+use calmram_lib::Money;
+use rocket::{http::Status, serde::json::Json};
+use rocket_db_pools::Connection;
+use serde::{Deserialize, Serialize};
+use sqlx::{postgres::types::PgMoney, types::time::OffsetDateTime, Connection as _, PgConnection};
+
+use crate::{adjustments::Manager, promotions::normalize, Db};
+
+#[derive(Debug)]
+pub enum GiftCardError {
+    Unknown(String),
+    Insufficient { code: String, balance: Money },
+    Db(sqlx::Error),
+}
+
+impl fmt::Display for GiftCardError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GiftCardError::Unknown(code) => write!(f, "{code} is not a gift card"),
+            GiftCardError::Insufficient { code, balance } => {
+                write!(f, "gift card {code} only has {balance} on it")
+            }
+            GiftCardError::Db(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for GiftCardError {}
+
+impl From<sqlx::Error> for GiftCardError {
+    fn from(err: sqlx::Error) -> Self {
+        GiftCardError::Db(err)
+    }
+}
+
+/// Why a card's balance changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EntryKind {
+    /// The card was sold with money on it.
+    Issue,
+    /// Part of an order was paid with the card.
+    Redeem,
+    /// An order paid with the card was voided or refunded.
+    Refund,
+}
+
+impl EntryKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            EntryKind::Issue => "issue",
+            EntryKind::Redeem => "redeem",
+            EntryKind::Refund => "refund",
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct GiftCard {
+    pub code: String,
+    pub balance: Money,
+}
+
+/// Looks up the card with `code` and what is on it, locking it until the end
+/// of the transaction `db` is in when `lock` is set.
+async fn balance(
+    db: &mut PgConnection,
+    code: &str,
+    lock: bool,
+) -> Result<(i32, GiftCard), GiftCardError> {
+    let code = normalize(code);
+    let query = if lock {
+        "SELECT id FROM gift_cards WHERE code = $1 FOR UPDATE"
+    } else {
+        "SELECT id FROM gift_cards WHERE code = $1"
+    };
+    let (id,): (i32,) = sqlx::query_as(query)
+        .bind(&code)
+        .fetch_optional(&mut *db)
+        .await?
+        .ok_or_else(|| GiftCardError::Unknown(code.clone()))?;
+
+    let (balance,): (Option<PgMoney>,) =
+        sqlx::query_as("SELECT SUM(amount) FROM gift_card_entries WHERE gift_card_id = $1")
+            .bind(id)
+            .fetch_one(db)
+            .await?;
+    Ok((
+        id,
+        GiftCard {
+            code,
+            balance: Money::from_cents(balance.map_or(0, |balance| balance.0)),
+        },
+    ))
+}
+
+/// Looks up what is on the card with `code`.
+pub async fn find(db: &mut PgConnection, code: &str) -> Result<GiftCard, GiftCardError> {
+    balance(db, code, false).await.map(|(_, card)| card)
+}
+
+/// Checks that the card with `code` has at least `amount` on it, returning
+/// its id. When `db` is in a transaction, the card stays locked until it
+/// ends, so it cannot be spent twice at once.
+pub async fn hold(db: &mut PgConnection, code: &str, amount: Money) -> Result<i32, GiftCardError> {
+    let (id, card) = balance(db, code, true).await?;
+    if card.balance < amount {
+        return Err(GiftCardError::Insufficient {
+            code: card.code,
+            balance: card.balance,
+        });
+    }
+    Ok(id)
+}
+
+/// Adds `amount` to the balance of card `gift_card_id`, or takes it off when
+/// negative, for the tender `tender_id` if there is one.
+pub async fn record(
+    db: &mut PgConnection,
+    gift_card_id: i32,
+    kind: EntryKind,
+    amount: Money,
+    tender_id: Option<i64>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO gift_card_entries (gift_card_id, kind, amount, tender_id, time)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(gift_card_id)
+    .bind(kind.as_str())
+    .bind(PgMoney(amount.cents()))
+    .bind(tender_id)
+    .bind(OffsetDateTime::now_utc())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[derive(Deserialize)]
+pub struct IssueRequest {
+    amount: Money,
+    /// The code printed on the card. One is made up when left out.
+    code: Option<String>,
+}
+
+fn error(err: GiftCardError) -> (Status, String) {
+    match err {
+        GiftCardError::Unknown(_) => (Status::NotFound, err.to_string()),
+        GiftCardError::Insufficient { .. } => (Status::UnprocessableEntity, err.to_string()),
+        GiftCardError::Db(err) => {
+            println!("Gift card database error: {}", err);
+            (
+                Status::InternalServerError,
+                "the database failed".to_string(),
+            )
+        }
+    }
+}
+
+fn db_error(err: sqlx::Error) -> (Status, String) {
+    error(GiftCardError::Db(err))
+}
+
+/// Issues a new gift card with `amount` on it.
+#[post("/gift_cards", data = "<request>")]
+pub async fn issue(
+    _manager: Manager,
+    request: Json<IssueRequest>,
+    mut db: Connection<Db>,
+) -> Result<Json<GiftCard>, (Status, String)> {
+    let IssueRequest { amount, code } = request.into_inner();
+    if amount <= Money::ZERO {
+        return Err((
+            Status::UnprocessableEntity,
+            "a gift card must be issued with more than $0.00 on it".to_string(),
+        ));
+    }
+
+    let mut tx = db.begin().await.map_err(db_error)?;
+    let inserted: Option<(i32, String)> = sqlx::query_as(
+        "INSERT INTO gift_cards (code, issued_at)
+         VALUES (COALESCE($1, upper(substr(md5(random()::TEXT), 1, 12))), $2)
+         ON CONFLICT (code) DO NOTHING
+         RETURNING id, code",
+    )
+    .bind(code.as_deref().map(normalize))
+    .bind(OffsetDateTime::now_utc())
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(db_error)?;
+    let (id, code) = inserted.ok_or_else(|| {
+        (
+            Status::Conflict,
+            "there is already a gift card with that code".to_string(),
+        )
+    })?;
+
+    record(&mut tx, id, EntryKind::Issue, amount, None)
+        .await
+        .map_err(db_error)?;
+    tx.commit().await.map_err(db_error)?;
+
+    println!("Issued gift card {} with {}", code, amount);
+    Ok(Json(GiftCard {
+        code,
+        balance: amount,
+    }))
+}
+
+/// Checks what is left on a gift card.
+#[get("/gift_cards/<code>")]
+pub async fn check(
+    _manager: Manager,
+    code: &str,
+    mut db: Connection<Db>,
+) -> Result<Json<GiftCard>, (Status, String)> {
+    find(&mut db, code).await.map(Json).map_err(error)
+}
@@ -0,0 +1,285 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use calmram_lib::{Cart, Menu, OrderError};
+
+struct Stock {
+    /// The count given in the menu config, to tell a restock from a reload
+    /// that left this item alone.
+    configured: u32,
+    remaining: u32,
+}
+
+/// Remaining stock of every menu item that has a `stock` count configured.
+/// Items without one are never counted down.
+pub struct Inventory {
+    stock: Mutex<HashMap<i32, Stock>>,
+}
+
+fn configured_stock(menu: &Menu) -> impl Iterator<Item = (i32, u32)> + '_ {
+    let items = menu.items().map(|item| (item.id, item.stock));
+    let options = menu
+        .items()
+        .flat_map(|item| item.options())
+        .map(|option| (option.id, option.stock));
+    items
+        .chain(options)
+        .filter_map(|(id, stock)| stock.map(|stock| (id, stock)))
+}
+
+/// How many of each item and option `cart` uses. Counts too large to add up
+/// are kept at the most there can be, which no stock covers.
+fn needed(cart: &Cart) -> HashMap<i32, u32> {
+    let mut needed = HashMap::new();
+    for item in &cart.items {
+        let options = item
+            .order
+            .options
+            .iter()
+            .map(|chosen| (chosen.id, chosen.quantity));
+        for (id, count) in std::iter::once((item.order.item, 1)).chain(options) {
+            let count = count.saturating_mul(item.quantity);
+            let needed = needed.entry(id).or_insert(0u32);
+            *needed = needed.saturating_add(count);
+        }
+    }
+    needed
+}
+
+impl Inventory {
+    pub fn new(menu: &Menu) -> Self {
+        let inventory = Inventory {
+            stock: Mutex::new(HashMap::new()),
+        };
+        inventory.restock(menu);
+        inventory
+    }
+
+    /// Picks up the stock counts of a newly loaded menu. Items whose
+    /// configured count changed are reset to it; the rest keep counting down
+    /// from where they were.
+    pub fn restock(&self, menu: &Menu) {
+        let mut stock = self.stock.lock().unwrap();
+        let previous = std::mem::take(&mut *stock);
+        for (id, configured) in configured_stock(menu) {
+            let remaining = match previous.get(&id) {
+                Some(item) if item.configured == configured => item.remaining,
+                _ => configured,
+            };
+            stock.insert(
+                id,
+                Stock {
+                    configured,
+                    remaining,
+                },
+            );
+        }
+    }
+
+    /// Fills in the remaining stock of each counted item and marks the ones
+    /// that ran out as sold out.
+    pub fn apply(&self, menu: &mut Menu) {
+        let stock = self.stock.lock().unwrap();
+        for menu_item in menu.items_mut() {
+            if let Some(item) = stock.get(&menu_item.id) {
+                menu_item.stock = Some(item.remaining);
+                menu_item.sold_out |= item.remaining == 0;
+            }
+            let options = menu_item
+                .modifier_groups
+                .iter_mut()
+                .flat_map(|group| &mut group.options);
+            for option in options {
+                if let Some(item) = stock.get(&option.id) {
+                    option.stock = Some(item.remaining);
+                    option.sold_out |= item.remaining == 0;
+                }
+            }
+        }
+    }
+
+    /// Takes every item and option `cart` uses out of stock, or nothing if
+    /// there is not enough of any of them left. Returns whether an item sold
+    /// out as a result.
+    pub fn take(&self, cart: &Cart) -> Result<bool, OrderError> {
+        let needed = needed(cart);
+        let mut stock = self.stock.lock().unwrap();
+        for (id, count) in &needed {
+            if matches!(stock.get(id), Some(item) if item.remaining < *count) {
+                return Err(OrderError::SoldOut(*id));
+            }
+        }
+
+        let mut sold_out = false;
+        for (id, count) in &needed {
+            if let Some(item) = stock.get_mut(id) {
+                item.remaining -= count;
+                sold_out |= item.remaining == 0;
+            }
+        }
+        Ok(sold_out)
+    }
+
+    /// Returns the items of a cart that was taken out of stock but never
+    /// completed.
+    pub fn put_back(&self, cart: &Cart) {
+        self.return_stock(&needed(cart));
+    }
+
+    /// Puts `used`, a count of each item and option id, back into stock.
+    /// Returns whether anything that had sold out is available again.
+    pub fn return_stock(&self, used: &HashMap<i32, u32>) -> bool {
+        let mut stock = self.stock.lock().unwrap();
+        let mut restocked = false;
+        for (id, count) in used {
+            if let Some(item) = stock.get_mut(id) {
+                restocked |= item.remaining == 0 && *count > 0;
+                item.remaining += count;
+            }
+        }
+        restocked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use calmram_lib::{CartItem, ChosenOption, Order};
+
+    use super::*;
+
+    const RAMEN: i32 = 1;
+    const EGG: i32 = 2;
+    const TEA: i32 = 3;
+
+    /// Ramen with an optional egg, and tea, with `tea` of the tea and `eggs`
+    /// of the eggs in stock. Ramen is never counted.
+    fn menu(tea: u32, eggs: u32) -> Menu {
+        serde_json::from_str(&format!(
+            r#"{{
+                "categories": [{{
+                    "name": "Food",
+                    "image_url": "food.png",
+                    "items": [
+                        {{
+                            "name": "Ramen",
+                            "price": 10.00,
+                            "image_url": "bowl.png",
+                            "id": {RAMEN},
+                            "modifier_groups": [{{
+                                "name": "Toppings",
+                                "options": [{{"name": "Egg", "id": {EGG}, "stock": {eggs}}}]
+                            }}]
+                        }},
+                        {{"name": "Tea", "price": 2.00, "image_url": "tea.png", "id": {TEA}, "stock": {tea}}}
+                    ]
+                }}]
+            }}"#
+        ))
+        .expect("the test menu parses")
+    }
+
+    fn cart(items: &[(i32, &[i32], u32)]) -> Cart {
+        Cart {
+            items: items
+                .iter()
+                .map(|&(item, options, quantity)| CartItem {
+                    order: Order {
+                        item,
+                        options: options
+                            .iter()
+                            .map(|&id| ChosenOption { id, quantity: 1 })
+                            .collect(),
+                    },
+                    quantity,
+                })
+                .collect(),
+        }
+    }
+
+    fn remaining(inventory: &Inventory, id: i32) -> Option<u32> {
+        let stock = inventory.stock.lock().unwrap();
+        stock.get(&id).map(|item| item.remaining)
+    }
+
+    #[test]
+    fn takes_the_whole_cart_or_nothing() {
+        let inventory = Inventory::new(&menu(5, 2));
+        let order = cart(&[(RAMEN, &[EGG], 3), (TEA, &[], 1)]);
+
+        assert!(matches!(
+            inventory.take(&order),
+            Err(OrderError::SoldOut(EGG))
+        ));
+        assert_eq!(remaining(&inventory, TEA), Some(5));
+        assert_eq!(remaining(&inventory, EGG), Some(2));
+        assert_eq!(remaining(&inventory, RAMEN), None);
+    }
+
+    #[test]
+    fn says_when_something_sells_out() {
+        let inventory = Inventory::new(&menu(5, 2));
+
+        assert!(!inventory.take(&cart(&[(RAMEN, &[EGG], 1)])).unwrap());
+        assert!(inventory
+            .take(&cart(&[(RAMEN, &[EGG], 1), (TEA, &[], 2)]))
+            .unwrap());
+        assert_eq!(remaining(&inventory, EGG), Some(0));
+        assert_eq!(remaining(&inventory, TEA), Some(3));
+
+        let mut offered = menu(5, 2);
+        inventory.apply(&mut offered);
+        let egg = offered
+            .items()
+            .flat_map(|item| item.options())
+            .next()
+            .unwrap();
+        assert!(egg.sold_out);
+    }
+
+    #[test]
+    fn puts_back_what_was_taken() {
+        let inventory = Inventory::new(&menu(5, 2));
+        let order = cart(&[(RAMEN, &[EGG], 2), (TEA, &[], 1)]);
+
+        inventory.take(&order).unwrap();
+        inventory.put_back(&order);
+        assert_eq!(remaining(&inventory, TEA), Some(5));
+        assert_eq!(remaining(&inventory, EGG), Some(2));
+    }
+
+    #[test]
+    fn returning_stock_says_when_something_is_back() {
+        let inventory = Inventory::new(&menu(5, 2));
+        inventory.take(&cart(&[(RAMEN, &[EGG], 2)])).unwrap();
+
+        let returned = HashMap::from([(TEA, 1)]);
+        assert!(!inventory.return_stock(&returned));
+        let returned = HashMap::from([(EGG, 1), (RAMEN, 1)]);
+        assert!(inventory.return_stock(&returned));
+        assert_eq!(remaining(&inventory, EGG), Some(1));
+        assert_eq!(remaining(&inventory, TEA), Some(6));
+    }
+
+    #[test]
+    fn restocks_items_whose_count_changed() {
+        let inventory = Inventory::new(&menu(5, 2));
+        inventory
+            .take(&cart(&[(RAMEN, &[EGG], 2), (TEA, &[], 3)]))
+            .unwrap();
+
+        inventory.restock(&menu(5, 10));
+        assert_eq!(remaining(&inventory, EGG), Some(10));
+        assert_eq!(remaining(&inventory, TEA), Some(2));
+    }
+
+    #[test]
+    fn reloading_the_same_counts_leaves_stock_alone() {
+        let inventory = Inventory::new(&menu(5, 2));
+        inventory
+            .take(&cart(&[(RAMEN, &[EGG], 1), (TEA, &[], 4)]))
+            .unwrap();
+
+        inventory.restock(&menu(5, 2));
+        assert_eq!(remaining(&inventory, EGG), Some(1));
+        assert_eq!(remaining(&inventory, TEA), Some(1));
+    }
+}
@@ -0,0 +1,345 @@
+use std::sync::Arc;
+
+use calmram_lib::{
+    check_tenders,
+    protocol::{self, ClientMessage, ServerMessage, Ticket},
+    quote, Money, Tender,
+};
+use rocket::{
+    futures::{SinkExt, StreamExt},
+    tokio::select,
+    State,
+};
+use rocket_db_pools::Connection;
+use sqlx::PgConnection;
+use ws::Message;
+
+use crate::{
+    config::MenuConfig,
+    gift_cards::{self, GiftCardError},
+    kitchen::Kitchen,
+    loyalty::{self, LoyaltyError, Visit},
+    orders,
+    payments::{self, PaymentProvider},
+    promotions::{self, PromoError},
+    registry::Registry,
+    Db,
+};
+
+fn reply(message: &ServerMessage) -> Message {
+    Message::text(protocol::encode(message))
+}
+
+/// The tenders an order is paid with. A kiosk that sends none pays the whole
+/// total by card, unless there is nothing to pay.
+fn tenders_for(tenders: Vec<Tender>, total: Money) -> Vec<Tender> {
+    if tenders.is_empty() && !total.is_zero() {
+        vec![Tender::Card { amount: total }]
+    } else {
+        tenders
+    }
+}
+
+#[get("/kiosk")]
+pub fn kiosk<'r>(
+    ws: ws::WebSocket,
+    menu_config: &'r State<Arc<MenuConfig>>,
+    kiosks: &'r State<Arc<Registry<ServerMessage>>>,
+    kitchen: &'r State<Kitchen>,
+    payments: &'r State<Arc<dyn PaymentProvider>>,
+    mut db: Connection<Db>,
+) -> ws::Channel<'r> {
+    ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut kiosk = kiosks.register();
+            info!("Kiosk {} connected ({} connected)", kiosk.id, kiosks.len());
+
+            loop {
+                select! {
+                    message = stream.next() => {
+                        let Some(message) = message else {
+                            break;
+                        };
+                        match message? {
+                            Message::Text(text) => match protocol::decode(&text) {
+                                Ok(message) => {
+                                    let response = respond(
+                                        message,
+                                        menu_config,
+                                        kiosks,
+                                        kitchen,
+                                        &***payments,
+                                        &mut db,
+                                    )
+                                    .await;
+                                    stream.send(reply(&response)).await?;
+                                }
+                                Err(err) => {
+                                    println!("Received unexpected message {:?}: {}", text, err)
+                                }
+                            },
+                            m => println!("Received unexpected message: {:?}", m),
+                        }
+                    }
+                    Some(message) = kiosk.receiver.recv() => {
+                        stream.send(reply(&message)).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    })
+}
+
+async fn respond(
+    message: ClientMessage,
+    menu_config: &MenuConfig,
+    kiosks: &Registry<ServerMessage>,
+    kitchen: &Kitchen,
+    payments: &dyn PaymentProvider,
+    db: &mut PgConnection,
+) -> ServerMessage {
+    match message {
+        ClientMessage::GetConfig => ServerMessage::MenuUpdated(menu_config.current()),
+        ClientMessage::CheckPromo(code) => match promotions::find(db, &code).await {
+            Ok(promotion) => ServerMessage::PromoAccepted(promotion),
+            Err(err) => {
+                println!("Rejected promo code {:?}: {}", code, err);
+                let reason = match err {
+                    PromoError::Db(_) => "the code could not be checked".to_string(),
+                    err => err.to_string(),
+                };
+                ServerMessage::PromoRejected { code, reason }
+            }
+        },
+        ClientMessage::CheckGiftCard(code) => match gift_cards::find(db, &code).await {
+            Ok(card) => ServerMessage::GiftCardBalance {
+                code: card.code,
+                balance: card.balance,
+            },
+            Err(err) => {
+                println!("Rejected gift card {:?}: {}", code, err);
+                let reason = match err {
+                    GiftCardError::Db(_) => "the gift card could not be checked".to_string(),
+                    err => err.to_string(),
+                };
+                ServerMessage::GiftCardRejected { code, reason }
+            }
+        },
+        ClientMessage::CheckLoyalty(phone) => match loyalty::points(db, &phone).await {
+            Ok(points) => ServerMessage::LoyaltyBalance { phone, points },
+            Err(err) => {
+                println!("Rejected phone number {:?}: {}", phone, err);
+                let reason = match err {
+                    LoyaltyError::Db(_) => "the points could not be checked".to_string(),
+                    err => err.to_string(),
+                };
+                ServerMessage::LoyaltyRejected { phone, reason }
+            }
+        },
+        ClientMessage::SubmitOrder {
+            cart,
+            promo_code,
+            tip,
+            tenders,
+            phone,
+            reward,
+        } => {
+            let promotion = match &promo_code {
+                Some(code) => match promotions::find(db, code).await {
+                    Ok(promotion) => Some(promotion),
+                    Err(err) => {
+                        println!("Rejected order {:?}: {}", cart, err);
+                        let reason = match err {
+                            PromoError::Db(_) => "the promo code could not be checked".to_string(),
+                            err => err.to_string(),
+                        };
+                        return ServerMessage::OrderRejected { reason };
+                    }
+                },
+                None => None,
+            };
+
+            let config = menu_config.current();
+            let phone = match phone.as_deref().map(loyalty::normalize).transpose() {
+                Ok(phone) => phone,
+                Err(err) => {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    return ServerMessage::OrderRejected {
+                        reason: err.to_string(),
+                    };
+                }
+            };
+            let reward = reward.map(|id| {
+                loyalty::reward(&config.loyalty, id, phone.as_deref(), promotion.is_some())
+            });
+            let reward = match reward.transpose() {
+                Ok(reward) => reward,
+                Err(err) => {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    return ServerMessage::OrderRejected {
+                        reason: err.to_string(),
+                    };
+                }
+            };
+
+            // A reward is priced like a promotion, in its place.
+            let discount = promotion
+                .clone()
+                .or_else(|| reward.as_ref().map(|reward| reward.promotion()));
+            let quote = match quote(&config, &cart, discount.as_ref(), tip) {
+                Ok(quote) => quote,
+                Err(err) => {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    return ServerMessage::OrderRejected {
+                        reason: err.to_string(),
+                    };
+                }
+            };
+            if let Some(reward) = &reward {
+                if let Err(err) = loyalty::check_discount(reward, quote.discount.as_ref()) {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    return ServerMessage::OrderRejected {
+                        reason: err.to_string(),
+                    };
+                }
+            }
+
+            let tenders = tenders_for(tenders, quote.total);
+            if let Err(err) = check_tenders(&tenders, quote.total) {
+                println!("Rejected order {:?}: {}", cart, err);
+                return ServerMessage::OrderRejected {
+                    reason: err.to_string(),
+                };
+            }
+
+            // Turn away gift cards without enough on them and rewards without
+            // enough points before anyone pays by card. Both are checked again
+            // when the order is saved.
+            if let (Some(phone), Some(reward)) = (&phone, &reward) {
+                if let Err(err) = loyalty::hold(db, phone, reward.points).await {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    let reason = match err {
+                        LoyaltyError::Db(_) => "the points could not be checked".to_string(),
+                        err => err.to_string(),
+                    };
+                    return ServerMessage::OrderRejected { reason };
+                }
+            }
+            for tender in &tenders {
+                if let Tender::GiftCard { code, amount } = tender {
+                    if let Err(err) = gift_cards::hold(db, code, *amount).await {
+                        println!("Rejected order {:?}: {}", cart, err);
+                        let reason = match err {
+                            GiftCardError::Db(_) => {
+                                "the gift card could not be checked".to_string()
+                            }
+                            err => err.to_string(),
+                        };
+                        return ServerMessage::OrderRejected { reason };
+                    }
+                }
+            }
+
+            let sold_out = match menu_config.inventory().take(&cart) {
+                Ok(sold_out) => sold_out,
+                Err(err) => {
+                    println!("Rejected order {:?}: {}", cart, err);
+                    return ServerMessage::OrderRejected {
+                        reason: err.to_string(),
+                    };
+                }
+            };
+
+            // Anything taken from stock is put back if the order falls through,
+            // so kiosks only need telling about it once the order is placed.
+            let paid = match payments::take(payments, &tenders).await {
+                Ok(paid) => paid,
+                Err(err) => {
+                    println!("Payment for order {:?} failed: {}", cart, err);
+                    menu_config.inventory().put_back(&cart);
+                    return ServerMessage::PaymentDeclined {
+                        reason: err.to_string(),
+                    };
+                }
+            };
+
+            let visit = phone.map(|phone| Visit {
+                phone,
+                earned: config.loyalty.points(&quote),
+                reward,
+            });
+            let saved = orders::insert_order(
+                db,
+                menu_config.clock().today(),
+                &quote,
+                promotion.as_ref(),
+                visit.as_ref(),
+                payments.name(),
+                &paid,
+            )
+            .await;
+            let response = match saved {
+                Ok((order_id, order_number)) => {
+                    println!("Received order #{}: {:?}", order_number, cart);
+                    kitchen.add(Ticket::new(order_id, order_number, &config.menu, &cart));
+                    ServerMessage::OrderAccepted {
+                        order_number,
+                        total: quote.total,
+                    }
+                }
+                Err(err) => {
+                    println!("Failed to save order {:?}: {}", cart, err);
+                    payments::give_back(payments, &paid).await;
+                    menu_config.inventory().put_back(&cart);
+                    let reason = if err.is_db() {
+                        "the order could not be saved".to_string()
+                    } else {
+                        err.to_string()
+                    };
+                    ServerMessage::OrderRejected { reason }
+                }
+            };
+
+            if sold_out {
+                kiosks.broadcast(&ServerMessage::MenuUpdated(menu_config.current()));
+            }
+            response
+        }
+        ClientMessage::Ping => ServerMessage::Pong,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pays_by_card_when_no_tenders_are_sent() {
+        let total = Money::from_cents(1250);
+        let tenders = tenders_for(Vec::new(), total);
+        assert_eq!(tenders, vec![Tender::Card { amount: total }]);
+        assert!(check_tenders(&tenders, total).is_ok());
+    }
+
+    #[test]
+    fn takes_no_tenders_for_a_zero_total_order() {
+        let tenders = tenders_for(Vec::new(), Money::ZERO);
+        assert!(tenders.is_empty());
+        assert!(check_tenders(&tenders, Money::ZERO).is_ok());
+    }
+
+    #[test]
+    fn keeps_the_tenders_sent() {
+        let total = Money::from_cents(1250);
+        let sent = vec![
+            Tender::Cash {
+                amount: Money::from_cents(250),
+            },
+            Tender::Card {
+                amount: Money::from_cents(1000),
+            },
+        ];
+        assert_eq!(tenders_for(sent.clone(), total), sent);
+    }
+}
@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use calmram_lib::{
+    protocol::{
+        self, BoardOrder, BoardServerMessage, KitchenClientMessage, KitchenServerMessage,
+        ServerMessage, Ticket,
+    },
+    OrderStatus,
+};
+use rocket::{
+    futures::{SinkExt, StreamExt},
+    tokio::select,
+    State,
+};
+use rocket_db_pools::Connection;
+use sqlx::PgConnection;
+use ws::Message;
+
+use crate::{orders, registry::Registry, Db};
+
+/// The kitchen screens and pickup boards following accepted orders.
+pub struct Kitchen {
+    screens: Registry<KitchenServerMessage>,
+    boards: Registry<BoardServerMessage>,
+}
+
+impl Kitchen {
+    pub fn new() -> Self {
+        Kitchen {
+            screens: Registry::new(),
+            boards: Registry::new(),
+        }
+    }
+
+    pub fn boards(&self) -> &Registry<BoardServerMessage> {
+        &self.boards
+    }
+
+    pub fn add(&self, ticket: Ticket) {
+        self.boards
+            .broadcast(&BoardServerMessage::OrderStatusChanged(BoardOrder {
+                order_number: ticket.order_number,
+                status: ticket.status,
+            }));
+        self.screens
+            .broadcast(&KitchenServerMessage::TicketAdded(ticket));
+    }
+
+    /// Moves an order along and tells every kitchen screen, pickup board and
+    /// kiosk about it.
+    pub async fn set_status(
+        &self,
+        kiosks: &Registry<ServerMessage>,
+        db: &mut PgConnection,
+        order_id: i64,
+        status: OrderStatus,
+    ) {
+        match orders::set_status(db, order_id, status).await {
+            Ok(order_number) => {
+                println!("Order #{} is now {}", order_number, status.as_str());
+                self.screens
+                    .broadcast(&KitchenServerMessage::StatusChanged { order_id, status });
+                self.boards
+                    .broadcast(&BoardServerMessage::OrderStatusChanged(BoardOrder {
+                        order_number,
+                        status,
+                    }));
+                kiosks.broadcast(&ServerMessage::OrderStatusChanged {
+                    order_number,
+                    status,
+                });
+            }
+            Err(err) => println!("Could not update order {}: {}", order_id, err),
+        }
+    }
+}
+
+fn reply(message: &KitchenServerMessage) -> Message {
+    Message::text(protocol::encode(message))
+}
+
+#[get("/kitchen")]
+pub fn kitchen<'r>(
+    ws: ws::WebSocket,
+    kitchen: &'r State<Kitchen>,
+    kiosks: &'r State<Arc<Registry<ServerMessage>>>,
+    mut db: Connection<Db>,
+) -> ws::Channel<'r> {
+    ws.channel(move |mut stream| {
+        Box::pin(async move {
+            let mut screen = kitchen.screens.register();
+            info!("Kitchen screen {} connected", screen.id);
+
+            match orders::open_tickets(&mut db).await {
+                Ok(tickets) => {
+                    stream
+                        .send(reply(&KitchenServerMessage::Tickets(tickets)))
+                        .await?
+                }
+                Err(err) => println!("Could not load open tickets: {}", err),
+            }
+
+            loop {
+                select! {
+                    message = stream.next() => {
+                        let Some(message) = message else {
+                            break;
+                        };
+                        match message? {
+                            Message::Text(text) => match protocol::decode(&text) {
+                                // Cancelling a paid order has to give the money back,
+                                // which needs the manager PIN, so it is only done by
+                                // voiding the order.
+                                Ok(KitchenClientMessage::SetStatus {
+                                    order_id,
+                                    status: OrderStatus::Cancelled,
+                                }) => println!(
+                                    "Refused to cancel order {} from a kitchen screen",
+                                    order_id
+                                ),
+                                Ok(KitchenClientMessage::SetStatus { order_id, status }) => {
+                                    kitchen.set_status(kiosks, &mut db, order_id, status).await
+                                }
+                                Err(err) => {
+                                    println!("Received unexpected message {:?}: {}", text, err)
+                                }
+                            },
+                            m => println!("Received unexpected message: {:?}", m),
+                        }
+                    }
+                    Some(message) = screen.receiver.recv() => {
+                        stream.send(reply(&message)).await?;
+                    }
+                }
+            }
+            Ok(())
+        })
+    })
+}
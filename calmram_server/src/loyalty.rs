@@ -0,0 +1,342 @@
+//! Loyalty points, which customers collect by giving their phone number at
+//! the kiosk and spend on the rewards in the menu config.
+//!
+//! As with gift cards, points are never stored. Every order that earns or
+//! spends them adds rows to `loyalty_entries`, and a customer's points are
+//! what those add up to.
+
+use std::{convert::TryFrom, error::Error, fmt};
+
+use calmram_lib::{LineItem, LoyaltyConfig, Money, Reward};
+use sqlx::{types::time::OffsetDateTime, PgConnection};
+
+#[derive(Debug)]
+pub enum LoyaltyError {
+    InvalidPhone(String),
+    /// A reward was asked for without a phone number to take the points from.
+    NoPhone,
+    UnknownReward(i32),
+    /// A reward was asked for along with a promo code.
+    WithPromotion,
+    /// A reward was asked for that takes nothing off the order, such as a
+    /// free tea on an order without one.
+    NothingOff(String),
+    NotEnoughPoints {
+        points: i64,
+        needed: u32,
+    },
+    /// A reward costs more points than can be recorded.
+    TooManyPoints(u32),
+    Db(sqlx::Error),
+}
+
+impl fmt::Display for LoyaltyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoyaltyError::InvalidPhone(phone) => write!(f, "{phone} is not a phone number"),
+            LoyaltyError::NoPhone => write!(f, "rewards need a phone number"),
+            LoyaltyError::UnknownReward(id) => write!(f, "reward {id} is not offered"),
+            LoyaltyError::WithPromotion => {
+                write!(f, "a reward cannot be used with a promo code")
+            }
+            LoyaltyError::NothingOff(name) => {
+                write!(f, "{name} takes nothing off this order")
+            }
+            LoyaltyError::NotEnoughPoints { points, needed } => {
+                write!(
+                    f,
+                    "that reward needs {needed} points but there are only {points}"
+                )
+            }
+            LoyaltyError::TooManyPoints(points) => {
+                write!(f, "{points} points are too many to spend at once")
+            }
+            LoyaltyError::Db(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for LoyaltyError {}
+
+impl From<sqlx::Error> for LoyaltyError {
+    fn from(err: sqlx::Error) -> Self {
+        LoyaltyError::Db(err)
+    }
+}
+
+/// Phone numbers are stored as just their digits, however they were typed.
+pub fn normalize(phone: &str) -> Result<String, LoyaltyError> {
+    let digits: String = phone.chars().filter(char::is_ascii_digit).collect();
+    if !(7..=15).contains(&digits.len()) {
+        return Err(LoyaltyError::InvalidPhone(phone.to_string()));
+    }
+    Ok(digits)
+}
+
+/// Looks up reward `id` for an order placed with `phone`, which must not
+/// also have a promo code.
+pub fn reward(
+    config: &LoyaltyConfig,
+    id: i32,
+    phone: Option<&str>,
+    promotion: bool,
+) -> Result<Reward, LoyaltyError> {
+    if phone.is_none() {
+        return Err(LoyaltyError::NoPhone);
+    }
+    if promotion {
+        return Err(LoyaltyError::WithPromotion);
+    }
+    config
+        .reward(id)
+        .cloned()
+        .ok_or(LoyaltyError::UnknownReward(id))
+}
+
+/// Checks that `reward`, priced as the `discount` line of an order, takes
+/// something off it, so no points are spent for nothing.
+pub fn check_discount(reward: &Reward, discount: Option<&LineItem>) -> Result<(), LoyaltyError> {
+    match discount {
+        Some(line) if line.price < Money::ZERO => Ok(()),
+        _ => Err(LoyaltyError::NothingOff(reward.name.clone())),
+    }
+}
+
+/// What a loyalty member's order does to their points.
+pub struct Visit {
+    /// As returned by [`normalize`].
+    pub phone: String,
+    pub reward: Option<Reward>,
+    pub earned: u32,
+}
+
+/// The points of the customer with `phone`, locking them until the end of
+/// the transaction `db` is in when `lock` is set. Customers who have never
+/// ordered have none.
+async fn balance(db: &mut PgConnection, phone: &str, lock: bool) -> sqlx::Result<i64> {
+    let query = if lock {
+        "SELECT id FROM customers WHERE phone = $1 FOR UPDATE"
+    } else {
+        "SELECT id FROM customers WHERE phone = $1"
+    };
+    let customer: Option<(i32,)> = sqlx::query_as(query)
+        .bind(phone)
+        .fetch_optional(&mut *db)
+        .await?;
+    let Some((id,)) = customer else {
+        return Ok(0);
+    };
+
+    let (points,): (Option<i64>,) =
+        sqlx::query_as("SELECT SUM(points) FROM loyalty_entries WHERE customer_id = $1")
+            .bind(id)
+            .fetch_one(db)
+            .await?;
+    Ok(points.unwrap_or_default())
+}
+
+/// The points of the customer with `phone`.
+pub async fn points(db: &mut PgConnection, phone: &str) -> Result<i64, LoyaltyError> {
+    Ok(balance(db, &normalize(phone)?, false).await?)
+}
+
+/// Checks that the customer with `phone` has at least `needed` points. When
+/// `db` is in a transaction, the customer stays locked until it ends, so the
+/// points cannot be spent twice at once.
+pub async fn hold(db: &mut PgConnection, phone: &str, needed: u32) -> Result<(), LoyaltyError> {
+    let points = balance(db, phone, true).await?;
+    if points < i64::from(needed) {
+        return Err(LoyaltyError::NotEnoughPoints { points, needed });
+    }
+    Ok(())
+}
+
+/// The id of the customer with `phone`, who joins the first time they order.
+pub async fn join(db: &mut PgConnection, phone: &str) -> sqlx::Result<i32> {
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO customers (phone, joined_at) VALUES ($1, $2)
+         ON CONFLICT (phone) DO UPDATE SET phone = EXCLUDED.phone
+         RETURNING id",
+    )
+    .bind(phone)
+    .bind(OffsetDateTime::now_utc())
+    .fetch_one(db)
+    .await?;
+    Ok(id)
+}
+
+async fn record(
+    db: &mut PgConnection,
+    customer_id: i32,
+    kind: &str,
+    points: i32,
+    order_id: i64,
+    reward_id: Option<i32>,
+) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO loyalty_entries (customer_id, kind, points, order_id, reward_id, time)
+         VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(customer_id)
+    .bind(kind)
+    .bind(points)
+    .bind(order_id)
+    .bind(reward_id)
+    .bind(OffsetDateTime::now_utc())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Takes the points `visit` spends off customer `customer_id` and adds the
+/// points it earns, both for order `order_id`. Orders earning more points
+/// than can be recorded earn as many as can be.
+pub async fn settle(
+    db: &mut PgConnection,
+    customer_id: i32,
+    visit: &Visit,
+    order_id: i64,
+) -> Result<(), LoyaltyError> {
+    if let Some(reward) = &visit.reward {
+        let points =
+            i32::try_from(reward.points).map_err(|_| LoyaltyError::TooManyPoints(reward.points))?;
+        record(
+            db,
+            customer_id,
+            "redeem",
+            -points,
+            order_id,
+            Some(reward.id),
+        )
+        .await?;
+    }
+    if visit.earned > 0 {
+        let earned = i32::try_from(visit.earned).unwrap_or(i32::MAX);
+        record(db, customer_id, "earn", earned, order_id, None).await?;
+    }
+    Ok(())
+}
+
+/// The part of the `earned` points of an order to take back once `refunded`
+/// of the `paid` for it has been given back.
+fn refunded_points(earned: i64, refunded: Money, paid: Money) -> i64 {
+    if paid <= Money::ZERO {
+        return 0;
+    }
+    let refunded = refunded.cents().clamp(0, paid.cents());
+    let points = i128::from(earned) * i128::from(refunded) / i128::from(paid.cents());
+    i64::try_from(points).unwrap_or(earned)
+}
+
+/// Takes back as much of the points order `order_id` earned as has been
+/// refunded, `refunded` in all of the `paid` for it, less what earlier
+/// refunds took back.
+pub async fn refund(
+    db: &mut PgConnection,
+    order_id: i64,
+    refunded: Money,
+    paid: Money,
+) -> sqlx::Result<()> {
+    let entries: Option<(i32, i64, i64)> = sqlx::query_as(
+        "SELECT customer_id,
+                COALESCE(SUM(points) FILTER (WHERE kind = 'earn'), 0),
+                COALESCE(-SUM(points) FILTER (WHERE kind = 'refund'), 0)
+         FROM loyalty_entries
+         WHERE order_id = $1
+         GROUP BY customer_id",
+    )
+    .bind(order_id)
+    .fetch_optional(&mut *db)
+    .await?;
+    let Some((customer_id, earned, taken)) = entries else {
+        return Ok(());
+    };
+
+    let points = refunded_points(earned, refunded, paid) - taken;
+    if points > 0 {
+        let points = i32::try_from(points).unwrap_or(i32::MAX);
+        record(db, customer_id, "refund", -points, order_id, None).await?;
+    }
+    Ok(())
+}
+
+/// Undoes what order `order_id` did to its customer's points, taking back
+/// what it earned and giving back what it spent.
+pub async fn void(db: &mut PgConnection, order_id: i64) -> sqlx::Result<()> {
+    sqlx::query(
+        "INSERT INTO loyalty_entries (customer_id, kind, points, order_id, reward_id, time)
+         SELECT customer_id, 'void', -points, order_id, reward_id, $2
+         FROM loyalty_entries
+         WHERE order_id = $1 AND kind <> 'void'",
+    )
+    .bind(order_id)
+    .bind(OffsetDateTime::now_utc())
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use calmram_lib::{Discount, LineKind};
+
+    use super::*;
+
+    fn free_tea() -> Reward {
+        Reward {
+            id: 1,
+            name: "Free tea".to_string(),
+            points: 50,
+            discount: Discount::FreeItem { item: 10 },
+        }
+    }
+
+    fn discount(cents: i64) -> LineItem {
+        LineItem {
+            kind: LineKind::Discount,
+            id: 1,
+            name: "Free tea".to_string(),
+            group: None,
+            quantity: 1,
+            price: Money::from_cents(cents),
+        }
+    }
+
+    #[test]
+    fn rewards_must_take_something_off() {
+        let reward = free_tea();
+        assert!(check_discount(&reward, Some(&discount(-200))).is_ok());
+        assert!(matches!(
+            check_discount(&reward, Some(&discount(0))),
+            Err(LoyaltyError::NothingOff(_))
+        ));
+        assert!(matches!(
+            check_discount(&reward, None),
+            Err(LoyaltyError::NothingOff(_))
+        ));
+    }
+
+    fn refunded(earned: i64, refunded: i64, paid: i64) -> i64 {
+        refunded_points(earned, Money::from_cents(refunded), Money::from_cents(paid))
+    }
+
+    #[test]
+    fn refunds_take_back_their_share_of_points() {
+        assert_eq!(refunded(100, 0, 2000), 0);
+        assert_eq!(refunded(100, 500, 2000), 25);
+        assert_eq!(refunded(100, 2000, 2000), 100);
+        // Rounded down, so a customer never loses more than they earned.
+        assert_eq!(refunded(10, 333, 1000), 3);
+    }
+
+    #[test]
+    fn refunds_never_take_back_more_than_was_earned() {
+        assert_eq!(refunded(100, 5000, 2000), 100);
+        assert_eq!(refunded(100, -500, 2000), 0);
+        assert_eq!(refunded(100, 500, 0), 0);
+        assert_eq!(
+            refunded(i64::from(i32::MAX), i64::MAX, i64::MAX),
+            i64::from(i32::MAX)
+        );
+    }
+}
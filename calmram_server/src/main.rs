@@ -1,16 +1,35 @@
-use calmram_lib::{Config, Menu, Order};
-use rocket::{time::Date, State};
-use rocket_db_pools::{Connection, Database};
-use sqlx::{
-    self,
-    postgres::types::PgMoney,
-    types::{time::OffsetDateTime, Decimal},
-    Row,
+use std::{path::PathBuf, sync::Arc};
+
+use adjustments::ManagerPin;
+use clock::Clock;
+use config::MenuConfig;
+use kitchen::Kitchen;
+use payments::{MockTerminal, PaymentProvider};
+use registry::Registry;
+use rocket::{
+    fairing::{self, AdHoc},
+    time::Date,
+    Build, Rocket,
 };
+use rocket_db_pools::{Connection, Database};
+use sqlx::{self, Row};
 
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use ws::Message;
+
+mod adjustments;
+mod board;
+mod clock;
+mod config;
+mod gift_cards;
+mod inventory;
+mod kiosk;
+mod kitchen;
+mod loyalty;
+mod orders;
+mod payments;
+mod promotions;
+mod registry;
+mod reports;
 
 #[macro_use]
 extern crate rocket;
@@ -34,51 +53,108 @@ async fn assets(file: &str) -> Option<rocket::fs::NamedFile> {
         .ok()
 }
 
-#[get("/kiosk")]
-async fn kiosk(
-    ws: ws::WebSocket,
-    config: &State<Config>,
-    mut db: Connection<Db>,
-) -> ws::Stream!['_] {
-    ws::Stream! { ws =>
-        for await message in ws {
-            match message? {
-                Message::Text(text) => {
-                    if text == "config" {
-                        yield Message::text(json!(config.inner()).to_string());
-                    } else if let Ok(complete_order) = serde_json::from_str::<Order>(&text) {
-                        let base_cost = config.menu.bases.iter().find(|base| base.id == complete_order.base).unwrap().price;
-                        let toppings_cost = complete_order.toppings.iter().fold(0.0, |acc, topping_id| {
-                            let topping = config.menu.toppings.iter().find(|topping| topping.id == *topping_id).unwrap();
-                            acc + topping.price.unwrap_or(0.0)
-                        });
-                        let total = PgMoney::from_decimal(Decimal::from_f32_retain(base_cost + toppings_cost).unwrap(), 2);
-                        sqlx::query("INSERT INTO orders (time, price) VALUES ($1, $2)")
-                            .bind(OffsetDateTime::now_utc())
-                            .bind(total)
-                            .execute(&mut **db)
-                            .await
-                            .unwrap();
-                        println!("Received order: {:?}", complete_order);
-                    } else {
-                        println!("Received unexpected message: {:?}", text);
-                    }
-                }
-                m => println!("Received unexpected message: {:?}", m),
-            }
-        }
-    }
-}
-
 #[launch]
 fn rocket() -> _ {
-    let config: Config = serde_json::from_str(include_str!("../Config.json")).unwrap();
-    rocket::build()
+    let rocket = rocket::build();
+
+    let menu_config_path: PathBuf = rocket
+        .figment()
+        .extract_inner("menu_config")
+        .unwrap_or_else(|_| PathBuf::from("Config.json"));
+    // Menu schedules and business days are kept in `utc_offset`, or in UTC
+    // without one.
+    let utc_offset: Option<String> = rocket.figment().extract_inner("utc_offset").ok();
+    let clock = match utc_offset.as_deref().map(Clock::parse).transpose() {
+        Ok(clock) => clock.unwrap_or_default(),
+        Err(err) => panic!("Invalid utc_offset: {}", err),
+    };
+
+    let menu_config = match MenuConfig::load(menu_config_path.clone(), clock) {
+        Ok(menu_config) => Arc::new(menu_config),
+        Err(err) => panic!(
+            "Could not load menu config from {}: {}",
+            menu_config_path.display(),
+            err
+        ),
+    };
+
+    let kiosks = Arc::new(Registry::new());
+
+    // Until a real terminal is supported, payments go to a mock one that
+    // works through `mock_terminal`, a list of "approve", "decline",
+    // "partial", "timeout" and "fail".
+    let mock_script: Vec<String> = rocket
+        .figment()
+        .extract_inner("mock_terminal")
+        .unwrap_or_default();
+    let mock_script = match mock_script.iter().map(|outcome| outcome.parse()).collect() {
+        Ok(script) => script,
+        Err(err) => panic!("Invalid mock_terminal: {}", err),
+    };
+    let payments: Arc<dyn PaymentProvider> = Arc::new(MockTerminal::new(mock_script));
+
+    // Voids, refunds and reports need `manager_pin`, and are refused
+    // without one.
+    let manager_pin: Option<String> = rocket.figment().extract_inner("manager_pin").ok();
+
+    rocket
         .attach(Db::init())
-        .manage(config)
-        .mount("/", routes![index, assets, kiosk])
+        .attach(AdHoc::try_on_ignite("SQL migrations", run_migrations))
+        .attach(AdHoc::on_liftoff("Menu config watcher", {
+            let menu_config = menu_config.clone();
+            let kiosks = kiosks.clone();
+            move |_| {
+                Box::pin(async move {
+                    rocket::tokio::spawn(menu_config.watch(kiosks));
+                })
+            }
+        }))
+        .manage(menu_config)
+        .manage(kiosks)
+        .manage(payments)
+        .manage(ManagerPin(manager_pin))
+        .manage(Kitchen::new())
+        .mount(
+            "/",
+            routes![
+                index,
+                assets,
+                kiosk::kiosk,
+                kitchen::kitchen,
+                board::board,
+                adjustments::void,
+                adjustments::refund,
+                adjustments::retry,
+                reports::sales,
+                reports::tips,
+                gift_cards::issue,
+                gift_cards::check,
+            ],
+        )
 }
 
 #[derive(Database)]
 #[database("db")]
 struct Db(sqlx::PgPool);
+
+/// Brings the database schema up to date with `migrations/`. sqlx records
+/// each applied migration in `_sqlx_migrations`.
+async fn run_migrations(rocket: Rocket<Build>) -> fairing::Result {
+    let Some(db) = Db::fetch(&rocket) else {
+        return Err(rocket);
+    };
+
+    let migrator = sqlx::migrate!();
+    match migrator.run(&**db).await {
+        Ok(()) => {
+            if let Some(latest) = migrator.iter().last() {
+                info!("Database schema is at version {}", latest.version);
+            }
+            Ok(rocket)
+        }
+        Err(err) => {
+            error!("Failed to migrate database: {}", err);
+            Err(rocket)
+        }
+    }
+}
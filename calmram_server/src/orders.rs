@@ -0,0 +1,444 @@
+use std::{collections::HashMap, convert::TryFrom, error::Error, fmt};
+
+use calmram_lib::{
+    protocol::{BoardOrder, Ticket, TicketItem, TicketModifier},
+    InvalidTransition, LineKind, Money, OrderStatus, Promotion, Quote,
+};
+use sqlx::{
+    postgres::types::PgMoney,
+    types::time::{Date, OffsetDateTime},
+    Connection, PgConnection,
+};
+
+use crate::{
+    gift_cards::{self, EntryKind, GiftCardError},
+    loyalty::{self, LoyaltyError, Visit},
+    payments::Payment,
+    promotions::{self, PromoError},
+};
+
+const OPEN_STATUSES: [OrderStatus; 3] = [
+    OrderStatus::Received,
+    OrderStatus::Preparing,
+    OrderStatus::Ready,
+];
+
+#[derive(Debug)]
+pub enum StatusError {
+    NotFound(i64),
+    Transition(InvalidTransition),
+    Db(sqlx::Error),
+}
+
+impl fmt::Display for StatusError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatusError::NotFound(order_id) => write!(f, "there is no order {order_id}"),
+            StatusError::Transition(err) => write!(f, "{err}"),
+            StatusError::Db(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for StatusError {}
+
+impl From<sqlx::Error> for StatusError {
+    fn from(err: sqlx::Error) -> Self {
+        StatusError::Db(err)
+    }
+}
+
+/// Why an order could not be saved.
+#[derive(Debug)]
+pub enum SaveError {
+    Promo(PromoError),
+    GiftCard(GiftCardError),
+    Loyalty(LoyaltyError),
+    /// A quantity too large for the database.
+    Quantity(u32),
+    Db(sqlx::Error),
+}
+
+impl SaveError {
+    /// Whether the database failed, rather than something the customer
+    /// can fix.
+    pub fn is_db(&self) -> bool {
+        matches!(
+            self,
+            SaveError::Db(_)
+                | SaveError::Promo(PromoError::Db(_))
+                | SaveError::GiftCard(GiftCardError::Db(_))
+                | SaveError::Loyalty(LoyaltyError::Db(_))
+        )
+    }
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Promo(err) => write!(f, "{err}"),
+            SaveError::GiftCard(err) => write!(f, "{err}"),
+            SaveError::Loyalty(err) => write!(f, "{err}"),
+            SaveError::Quantity(quantity) => write!(f, "{quantity} is too many to order"),
+            SaveError::Db(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<PromoError> for SaveError {
+    fn from(err: PromoError) -> Self {
+        SaveError::Promo(err)
+    }
+}
+
+impl From<GiftCardError> for SaveError {
+    fn from(err: GiftCardError) -> Self {
+        SaveError::GiftCard(err)
+    }
+}
+
+impl From<LoyaltyError> for SaveError {
+    fn from(err: LoyaltyError) -> Self {
+        SaveError::Loyalty(err)
+    }
+}
+
+/// `quantity` as the database stores it.
+fn quantity(quantity: u32) -> Result<i32, SaveError> {
+    i32::try_from(quantity).map_err(|_| SaveError::Quantity(quantity))
+}
+
+impl From<sqlx::Error> for SaveError {
+    fn from(err: sqlx::Error) -> Self {
+        SaveError::Db(err)
+    }
+}
+
+fn parse_status(status: &str) -> sqlx::Result<OrderStatus> {
+    status
+        .parse()
+        .map_err(|err: String| sqlx::Error::Decode(err.into()))
+}
+
+async fn record_status(
+    db: &mut PgConnection,
+    order_id: i64,
+    status: OrderStatus,
+) -> sqlx::Result<()> {
+    sqlx::query("INSERT INTO order_status_changes (order_id, status, time) VALUES ($1, $2, $3)")
+        .bind(order_id)
+        .bind(status.as_str())
+        .bind(OffsetDateTime::now_utc())
+        .execute(db)
+        .await?;
+    Ok(())
+}
+
+/// Saves an accepted cart, each of its items, each of their priced lines, its
+/// taxes and its tip in one transaction, along with the tenders it was paid
+/// with, returning the new order's id and the next order number of
+/// `business_day`. The promotion `quote` was priced with is
+/// used up, gift card tenders are taken off their cards and the customer's
+/// points are settled in the same transaction.
+pub async fn insert_order(
+    db: &mut PgConnection,
+    business_day: Date,
+    quote: &Quote,
+    promotion: Option<&Promotion>,
+    visit: Option<&Visit>,
+    provider: &str,
+    payments: &[Payment],
+) -> Result<(i64, u32), SaveError> {
+    let mut tx = db.begin().await?;
+
+    // The day's row stays locked until the order is saved, so no two orders
+    // get the same number.
+    let (order_number,): (i32,) = sqlx::query_as(
+        "INSERT INTO order_number_days (day, last_number) VALUES ($1, 1)
+         ON CONFLICT (day) DO UPDATE SET last_number = order_number_days.last_number + 1
+         RETURNING last_number",
+    )
+    .bind(business_day)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if let Some(promotion) = promotion {
+        promotions::redeem(&mut tx, promotion).await?;
+    }
+
+    let customer_id = match visit {
+        Some(visit) => {
+            if let Some(reward) = &visit.reward {
+                loyalty::hold(&mut tx, &visit.phone, reward.points).await?;
+            }
+            Some(loyalty::join(&mut tx, &visit.phone).await?)
+        }
+        None => None,
+    };
+
+    let discount = quote
+        .discount
+        .as_ref()
+        .map_or(Money::ZERO, |line| -line.price);
+    let (order_id,): (i64,) = sqlx::query_as(
+        "INSERT INTO orders
+             (order_number, business_day, time, subtotal, promotion_id, discount, tax,
+              tax_inclusive, tip, price, customer_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+         RETURNING id",
+    )
+    .bind(order_number)
+    .bind(business_day)
+    .bind(OffsetDateTime::now_utc())
+    .bind(PgMoney(quote.subtotal.cents()))
+    .bind(promotion.map(|promotion| promotion.id))
+    .bind(PgMoney(discount.cents()))
+    .bind(PgMoney(quote.tax.cents()))
+    .bind(quote.tax_inclusive)
+    .bind(PgMoney(quote.tip.cents()))
+    .bind(PgMoney(quote.total.cents()))
+    .bind(customer_id)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    for tax in &quote.taxes {
+        sqlx::query(
+            "INSERT INTO order_taxes (order_id, name, percent, amount)
+             VALUES ($1, $2, $3::numeric / 1000, $4)",
+        )
+        .bind(order_id)
+        .bind(&tax.name)
+        .bind(tax.percent.thousandths() as i32)
+        .bind(PgMoney(tax.amount.cents()))
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    for (position, item) in quote.items.iter().enumerate() {
+        let (cart_item_id,): (i64,) = sqlx::query_as(
+            "INSERT INTO order_cart_items (order_id, position, quantity, unit_price)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id",
+        )
+        .bind(order_id)
+        .bind(position as i32)
+        .bind(quantity(item.quantity)?)
+        .bind(PgMoney(item.unit_price.cents()))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for line in &item.lines {
+            sqlx::query(
+                "INSERT INTO order_items
+                     (order_id, cart_item_id, kind, item_id, name, group_name, quantity,
+                      unit_price)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+            )
+            .bind(order_id)
+            .bind(cart_item_id)
+            .bind(line.kind.as_str())
+            .bind(line.id)
+            .bind(&line.name)
+            .bind(&line.group)
+            .bind(quantity(line.quantity)?)
+            .bind(PgMoney(line.price.cents()))
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    for payment in payments {
+        // Only cards go through `provider`.
+        let card = match payment {
+            Payment::Card(authorization) => Some((provider, &authorization.reference)),
+            Payment::Cash(_) | Payment::GiftCard { .. } => None,
+        };
+        let gift_card_id = match payment {
+            Payment::GiftCard { code, amount } => {
+                Some(gift_cards::hold(&mut tx, code, *amount).await?)
+            }
+            _ => None,
+        };
+        let (tender_id,): (i64,) = sqlx::query_as(
+            "INSERT INTO tenders (order_id, kind, provider, reference, gift_card_id, amount, time)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING id",
+        )
+        .bind(order_id)
+        .bind(payment.kind())
+        .bind(card.map(|(provider, _)| provider))
+        .bind(card.map(|(_, reference)| reference))
+        .bind(gift_card_id)
+        .bind(PgMoney(payment.amount().cents()))
+        .bind(OffsetDateTime::now_utc())
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if let Some(gift_card_id) = gift_card_id {
+            gift_cards::record(
+                &mut tx,
+                gift_card_id,
+                EntryKind::Redeem,
+                -payment.amount(),
+                Some(tender_id),
+            )
+            .await?;
+        }
+    }
+
+    if let (Some(visit), Some(customer_id)) = (visit, customer_id) {
+        if let Some(reward) = &visit.reward {
+            loyalty::check_discount(reward, quote.discount.as_ref())?;
+        }
+        loyalty::settle(&mut tx, customer_id, visit, order_id).await?;
+    }
+
+    record_status(&mut tx, order_id, OrderStatus::Received).await?;
+
+    tx.commit().await?;
+    Ok((order_id, order_number as u32))
+}
+
+/// Moves an order to `status`, recording when it happened, and returns the
+/// order's number.
+pub async fn set_status(
+    db: &mut PgConnection,
+    order_id: i64,
+    status: OrderStatus,
+) -> Result<u32, StatusError> {
+    let mut tx = db.begin().await?;
+
+    let (current, order_number): (String, Option<i32>) =
+        sqlx::query_as("SELECT status, order_number FROM orders WHERE id = $1 FOR UPDATE")
+            .bind(order_id)
+            .fetch_optional(&mut *tx)
+            .await?
+            .ok_or(StatusError::NotFound(order_id))?;
+    parse_status(&current)?
+        .transition(status)
+        .map_err(StatusError::Transition)?;
+
+    sqlx::query("UPDATE orders SET status = $2 WHERE id = $1")
+        .bind(order_id)
+        .bind(status.as_str())
+        .execute(&mut *tx)
+        .await?;
+    record_status(&mut tx, order_id, status).await?;
+
+    tx.commit().await?;
+    Ok(order_number.unwrap_or_default() as u32)
+}
+
+#[derive(sqlx::FromRow)]
+struct TicketRow {
+    order_id: i64,
+    order_number: Option<i32>,
+    status: String,
+    cart_item_id: i64,
+    quantity: i32,
+    kind: String,
+    name: String,
+    group_name: Option<String>,
+    line_quantity: i32,
+}
+
+/// Tickets for every order the kitchen has not finished with, oldest first.
+pub async fn open_tickets(db: &mut PgConnection) -> sqlx::Result<Vec<Ticket>> {
+    let open_statuses: Vec<&str> = OPEN_STATUSES.iter().map(|status| status.as_str()).collect();
+    let rows: Vec<TicketRow> = sqlx::query_as(
+        "SELECT orders.id AS order_id, orders.order_number, orders.status,
+                order_cart_items.id AS cart_item_id, order_cart_items.quantity,
+                order_items.kind, order_items.name, order_items.group_name,
+                order_items.quantity AS line_quantity
+         FROM orders
+         JOIN order_cart_items ON order_cart_items.order_id = orders.id
+         JOIN order_items ON order_items.cart_item_id = order_cart_items.id
+         WHERE orders.status = ANY($1)
+         ORDER BY orders.id, order_cart_items.position, order_items.id",
+    )
+    .bind(open_statuses)
+    .fetch_all(db)
+    .await?;
+
+    let mut tickets: Vec<Ticket> = Vec::new();
+    let mut current_item = None;
+    for row in rows {
+        if tickets.last().map(|ticket| ticket.order_id) != Some(row.order_id) {
+            tickets.push(Ticket {
+                order_id: row.order_id,
+                order_number: row.order_number.unwrap_or_default() as u32,
+                items: Vec::new(),
+                status: parse_status(&row.status)?,
+            });
+        }
+
+        let ticket = tickets.last_mut().unwrap();
+        if current_item != Some(row.cart_item_id) {
+            current_item = Some(row.cart_item_id);
+            ticket.items.push(TicketItem {
+                quantity: row.quantity as u32,
+                name: String::new(),
+                modifiers: Vec::new(),
+            });
+        }
+
+        let item = ticket.items.last_mut().unwrap();
+        if row.kind == LineKind::Item.as_str() {
+            item.name = row.name;
+        } else if row.kind == LineKind::Modifier.as_str() {
+            item.modifiers.push(TicketModifier {
+                group: row.group_name.unwrap_or_default(),
+                name: row.name,
+                quantity: row.line_quantity as u32,
+            });
+        }
+    }
+    Ok(tickets)
+}
+
+/// How many of each item and option `order_id` took out of stock.
+pub async fn stock_used(db: &mut PgConnection, order_id: i64) -> sqlx::Result<HashMap<i32, u32>> {
+    let rows: Vec<(i32, String, i32, i32)> = sqlx::query_as(
+        "SELECT order_cart_items.quantity, order_items.kind, order_items.item_id,
+                order_items.quantity
+         FROM order_cart_items
+         JOIN order_items ON order_items.cart_item_id = order_cart_items.id
+         WHERE order_cart_items.order_id = $1",
+    )
+    .bind(order_id)
+    .fetch_all(db)
+    .await?;
+
+    let mut used = HashMap::new();
+    for (quantity, kind, id, line_quantity) in rows {
+        if kind == LineKind::Item.as_str() || kind == LineKind::Modifier.as_str() {
+            *used.entry(id).or_insert(0) += (quantity * line_quantity) as u32;
+        }
+    }
+    Ok(used)
+}
+
+/// The number and status of every order the kitchen has not finished with,
+/// oldest first.
+pub async fn open_orders(db: &mut PgConnection) -> sqlx::Result<Vec<BoardOrder>> {
+    let open_statuses: Vec<&str> = OPEN_STATUSES.iter().map(|status| status.as_str()).collect();
+    let rows: Vec<(Option<i32>, String)> = sqlx::query_as(
+        "SELECT order_number, status FROM orders
+         WHERE status = ANY($1)
+         ORDER BY id",
+    )
+    .bind(open_statuses)
+    .fetch_all(db)
+    .await?;
+
+    rows.into_iter()
+        .map(|(order_number, status)| {
+            Ok(BoardOrder {
+                order_number: order_number.unwrap_or_default() as u32,
+                status: parse_status(&status)?,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,347 @@
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use calmram_lib::{Money, Tender};
+use rocket::{
+    futures::future::{self, BoxFuture},
+    tokio::time,
+};
+
+/// How long a customer has to pay at the terminal.
+pub const PAYMENT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Money held on a customer's card, to be captured once their order is
+/// saved.
+#[derive(Clone, Debug)]
+pub struct Authorization {
+    /// The provider's reference for the payment.
+    pub reference: String,
+    /// What was approved, which can be less than was asked for.
+    pub amount: Money,
+}
+
+/// One tender an order was paid with.
+#[derive(Clone, Debug)]
+pub enum Payment {
+    Card(Authorization),
+    /// Owed in cash at the counter.
+    Cash(Money),
+    /// Taken off a gift card when the order is saved.
+    GiftCard {
+        code: String,
+        amount: Money,
+    },
+}
+
+impl Payment {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Payment::Card(_) => "card",
+            Payment::Cash(_) => "cash",
+            Payment::GiftCard { .. } => "gift_card",
+        }
+    }
+
+    pub fn amount(&self) -> Money {
+        match self {
+            Payment::Card(authorization) => authorization.amount,
+            Payment::Cash(amount) | Payment::GiftCard { amount, .. } => *amount,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum PaymentError {
+    Declined(String),
+    TimedOut,
+    /// The provider could not be reached or failed in some other way.
+    Provider(String),
+}
+
+impl fmt::Display for PaymentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaymentError::Declined(reason) => write!(f, "the payment was declined: {reason}"),
+            PaymentError::TimedOut => write!(f, "the payment timed out"),
+            PaymentError::Provider(err) => write!(f, "the payment failed: {err}"),
+        }
+    }
+}
+
+impl Error for PaymentError {}
+
+/// Something that can take card payments, like the terminal attached to a
+/// kiosk.
+pub trait PaymentProvider: Send + Sync {
+    /// The name payments are recorded under.
+    fn name(&self) -> &str;
+
+    /// Asks the customer to pay `amount` and holds it on their card. Some
+    /// cards only have part of it approved.
+    fn authorize(&self, amount: Money) -> BoxFuture<'_, Result<Authorization, PaymentError>>;
+
+    /// Takes the money held by `authorization`.
+    fn capture<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+    ) -> BoxFuture<'a, Result<(), PaymentError>>;
+
+    /// Releases the money held by an `authorization` that was not captured.
+    fn void<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+    ) -> BoxFuture<'a, Result<(), PaymentError>>;
+
+    /// Gives back `amount` of a captured payment.
+    fn refund<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+        amount: Money,
+    ) -> BoxFuture<'a, Result<(), PaymentError>>;
+}
+
+/// What the [`MockTerminal`] does with the next payment.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MockOutcome {
+    Approve,
+    Decline,
+    /// Approves half, as if the card did not have enough on it.
+    Partial,
+    /// Never answers, as if the customer walked away.
+    Timeout,
+    /// Fails as if the terminal were unplugged.
+    Fail,
+}
+
+impl FromStr for MockOutcome {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "approve" => Ok(MockOutcome::Approve),
+            "decline" => Ok(MockOutcome::Decline),
+            "partial" => Ok(MockOutcome::Partial),
+            "timeout" => Ok(MockOutcome::Timeout),
+            "fail" => Ok(MockOutcome::Fail),
+            _ => Err(format!("unknown mock terminal outcome {s:?}")),
+        }
+    }
+}
+
+/// A pretend card terminal for trying out the kiosk without charging anyone.
+///
+/// It works through a script of outcomes, one per payment, and starts over
+/// when it reaches the end. An empty script approves everything.
+pub struct MockTerminal {
+    script: Vec<MockOutcome>,
+    upcoming: Mutex<VecDeque<MockOutcome>>,
+    next_reference: AtomicU64,
+}
+
+/// How long the mock terminal takes to answer, like a customer tapping
+/// their card.
+const MOCK_DELAY: Duration = Duration::from_secs(2);
+
+impl MockTerminal {
+    pub fn new(script: Vec<MockOutcome>) -> Self {
+        MockTerminal {
+            upcoming: Mutex::new(script.iter().copied().collect()),
+            script,
+            next_reference: AtomicU64::new(1),
+        }
+    }
+
+    fn next_outcome(&self) -> MockOutcome {
+        let mut upcoming = self.upcoming.lock().unwrap();
+        if upcoming.is_empty() {
+            upcoming.extend(&self.script);
+        }
+        upcoming.pop_front().unwrap_or(MockOutcome::Approve)
+    }
+}
+
+impl PaymentProvider for MockTerminal {
+    fn name(&self) -> &str {
+        "mock"
+    }
+
+    fn authorize(&self, amount: Money) -> BoxFuture<'_, Result<Authorization, PaymentError>> {
+        let outcome = self.next_outcome();
+        Box::pin(async move {
+            match outcome {
+                MockOutcome::Approve | MockOutcome::Partial => {
+                    time::sleep(MOCK_DELAY).await;
+                    let approved = match outcome {
+                        MockOutcome::Partial => Money::from_cents(amount.cents() / 2),
+                        _ => amount,
+                    };
+                    let reference = self.next_reference.fetch_add(1, Ordering::Relaxed);
+                    info!("Mock terminal approved {} of {}", approved, amount);
+                    Ok(Authorization {
+                        reference: format!("mock-{reference}"),
+                        amount: approved,
+                    })
+                }
+                MockOutcome::Decline => {
+                    time::sleep(MOCK_DELAY).await;
+                    info!("Mock terminal declined {}", amount);
+                    Err(PaymentError::Declined("card declined".to_string()))
+                }
+                MockOutcome::Timeout => future::pending().await,
+                MockOutcome::Fail => Err(PaymentError::Provider(
+                    "the mock terminal is not connected".to_string(),
+                )),
+            }
+        })
+    }
+
+    fn capture<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+    ) -> BoxFuture<'a, Result<(), PaymentError>> {
+        info!("Mock terminal captured {}", authorization.reference);
+        Box::pin(future::ok(()))
+    }
+
+    fn void<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+    ) -> BoxFuture<'a, Result<(), PaymentError>> {
+        info!("Mock terminal voided {}", authorization.reference);
+        Box::pin(future::ok(()))
+    }
+
+    fn refund<'a>(
+        &'a self,
+        authorization: &'a Authorization,
+        amount: Money,
+    ) -> BoxFuture<'a, Result<(), PaymentError>> {
+        info!(
+            "Mock terminal refunded {} of {}",
+            amount, authorization.reference
+        );
+        Box::pin(future::ok(()))
+    }
+}
+
+/// Takes payment of `amount`, giving the customer [`PAYMENT_TIMEOUT`] to do
+/// so.
+pub async fn authorize(
+    provider: &dyn PaymentProvider,
+    amount: Money,
+) -> Result<Authorization, PaymentError> {
+    time::timeout(PAYMENT_TIMEOUT, provider.authorize(amount))
+        .await
+        .unwrap_or(Err(PaymentError::TimedOut))
+}
+
+/// Takes payment for every one of `tenders`, apart from gift cards, which
+/// are paid from when the order is saved.
+///
+/// Each card tender is paid at the terminal. When a card only has part of
+/// its tender approved, the customer is asked for another card for the
+/// rest. Nothing is captured until every card has been approved, and if any
+/// of them fails, the cards before it are voided or refunded so the customer
+/// is not charged for an order that was not placed.
+pub async fn take(
+    provider: &dyn PaymentProvider,
+    tenders: &[Tender],
+) -> Result<Vec<Payment>, PaymentError> {
+    let mut payments = Vec::new();
+    for tender in tenders {
+        let mut remaining = match tender {
+            Tender::Card { amount } => *amount,
+            Tender::Cash { amount } => {
+                payments.push(Payment::Cash(*amount));
+                continue;
+            }
+            Tender::GiftCard { code, amount } => {
+                payments.push(Payment::GiftCard {
+                    code: code.clone(),
+                    amount: *amount,
+                });
+                continue;
+            }
+        };
+        while remaining > Money::ZERO {
+            let authorization = match authorize(provider, remaining).await {
+                Ok(authorization) if authorization.amount.is_zero() => {
+                    release(provider, &authorization).await;
+                    Err(PaymentError::Declined("nothing was approved".to_string()))
+                }
+                result => result,
+            };
+            let authorization = match authorization {
+                Ok(authorization) => authorization,
+                Err(err) => {
+                    release_all(provider, &payments).await;
+                    return Err(err);
+                }
+            };
+            remaining -= authorization.amount.min(remaining);
+            if remaining > Money::ZERO {
+                info!("{} is still to pay on another card", remaining);
+            }
+            payments.push(Payment::Card(authorization));
+        }
+    }
+
+    for (captured, payment) in payments.iter().enumerate() {
+        let Payment::Card(authorization) = payment else {
+            continue;
+        };
+        if let Err(err) = provider.capture(authorization).await {
+            println!(
+                "Could not capture payment {}: {}",
+                authorization.reference, err
+            );
+            give_back(provider, &payments[..captured]).await;
+            release_all(provider, &payments[captured..]).await;
+            return Err(err);
+        }
+    }
+    Ok(payments)
+}
+
+/// Voids an `authorization` that will not be captured.
+async fn release(provider: &dyn PaymentProvider, authorization: &Authorization) {
+    if let Err(err) = provider.void(authorization).await {
+        println!(
+            "Could not void payment {}: {}",
+            authorization.reference, err
+        );
+    }
+}
+
+/// Voids every card of `payments`, none of which have been captured.
+async fn release_all(provider: &dyn PaymentProvider, payments: &[Payment]) {
+    for payment in payments {
+        if let Payment::Card(authorization) = payment {
+            release(provider, authorization).await;
+        }
+    }
+}
+
+/// Refunds every card of `payments` in full, for orders that fell through
+/// after being paid for.
+pub async fn give_back(provider: &dyn PaymentProvider, payments: &[Payment]) {
+    for payment in payments {
+        if let Payment::Card(authorization) = payment {
+            if let Err(err) = provider.refund(authorization, authorization.amount).await {
+                println!(
+                    "Could not refund payment {}: {}",
+                    authorization.reference, err
+                );
+            }
+        }
+    }
+}
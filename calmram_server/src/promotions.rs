@@ -0,0 +1,147 @@
+use std::{error::Error, fmt};
+
+use calmram_lib::{Discount, Money, Percent, Promotion};
+use sqlx::{postgres::types::PgMoney, types::time::OffsetDateTime, PgConnection};
+
+#[derive(Debug)]
+pub enum PromoError {
+    Unknown(String),
+    NotStarted(String),
+    Expired(String),
+    UsedUp(String),
+    /// The row for the code does not describe a discount, such as a
+    /// `percent_off` promotion without a `percent` from 0 to 100.
+    Misconfigured(String),
+    Db(sqlx::Error),
+}
+
+impl fmt::Display for PromoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromoError::Unknown(code) => write!(f, "{code} is not a promo code"),
+            PromoError::NotStarted(code) => write!(f, "{code} cannot be used yet"),
+            PromoError::Expired(code) => write!(f, "{code} has expired"),
+            PromoError::UsedUp(code) => write!(f, "{code} has been used up"),
+            PromoError::Misconfigured(code) => write!(f, "{code} cannot be used"),
+            PromoError::Db(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for PromoError {}
+
+impl From<sqlx::Error> for PromoError {
+    fn from(err: sqlx::Error) -> Self {
+        PromoError::Db(err)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct PromotionRow {
+    id: i32,
+    code: String,
+    name: String,
+    kind: String,
+    percent_thousandths: Option<i32>,
+    amount: Option<PgMoney>,
+    item_id: Option<i32>,
+    option_id: Option<i32>,
+    starts_at: Option<OffsetDateTime>,
+    ends_at: Option<OffsetDateTime>,
+    max_uses: Option<i32>,
+    uses: i32,
+}
+
+impl PromotionRow {
+    fn discount(&self) -> Option<Discount> {
+        match self.kind.as_str() {
+            "percent_off" => self
+                .percent_thousandths
+                .and_then(Percent::checked_from_thousandths)
+                .map(|percent| Discount::PercentOff { percent }),
+            "amount_off" => {
+                self.amount
+                    .filter(|amount| amount.0 >= 0)
+                    .map(|amount| Discount::AmountOff {
+                        amount: Money::from_cents(amount.0),
+                    })
+            }
+            "buy_one_get_one" => self.item_id.map(|item| Discount::BuyOneGetOne { item }),
+            "free_option" => self.option_id.map(|option| Discount::FreeOption { option }),
+            _ => None,
+        }
+    }
+}
+
+/// Codes are typed on the kiosk in whatever case and stored in upper case.
+pub fn normalize(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+/// Looks up `code` and checks that it can be used right now.
+pub async fn find(db: &mut PgConnection, code: &str) -> Result<Promotion, PromoError> {
+    let code = normalize(code);
+    let row: PromotionRow = sqlx::query_as(
+        "SELECT id, code, name, kind, (percent * 1000)::INTEGER AS percent_thousandths,
+                amount, item_id, option_id, starts_at, ends_at, max_uses, uses
+         FROM promotions
+         WHERE code = $1",
+    )
+    .bind(&code)
+    .fetch_optional(db)
+    .await?
+    .ok_or_else(|| PromoError::Unknown(code.clone()))?;
+
+    let now = OffsetDateTime::now_utc();
+    if row.starts_at.is_some_and(|starts_at| now < starts_at) {
+        return Err(PromoError::NotStarted(code));
+    }
+    if row.ends_at.is_some_and(|ends_at| now >= ends_at) {
+        return Err(PromoError::Expired(code));
+    }
+    if row.max_uses.is_some_and(|max_uses| row.uses >= max_uses) {
+        return Err(PromoError::UsedUp(code));
+    }
+
+    let discount = row.discount().ok_or(PromoError::Misconfigured(code))?;
+    Ok(Promotion {
+        id: row.id,
+        code: row.code,
+        name: row.name,
+        discount,
+    })
+}
+
+/// Gives back the use of a promotion counted by order `order_id`, which was
+/// voided.
+pub async fn release(db: &mut PgConnection, order_id: i64) -> sqlx::Result<()> {
+    sqlx::query(
+        "UPDATE promotions SET uses = uses - 1
+         WHERE id = (SELECT promotion_id FROM orders WHERE id = $1) AND uses > 0",
+    )
+    .bind(order_id)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Counts one use of `promotion`, failing if it was used up or is no longer
+/// running since it was looked up.
+pub async fn redeem(db: &mut PgConnection, promotion: &Promotion) -> Result<(), PromoError> {
+    let redeemed = sqlx::query(
+        "UPDATE promotions SET uses = uses + 1
+         WHERE id = $1
+           AND (max_uses IS NULL OR uses < max_uses)
+           AND (starts_at IS NULL OR starts_at <= now())
+           AND (ends_at IS NULL OR ends_at > now())",
+    )
+    .bind(promotion.id)
+    .execute(db)
+    .await?
+    .rows_affected();
+
+    if redeemed == 0 {
+        return Err(PromoError::UsedUp(promotion.code.clone()));
+    }
+    Ok(())
+}
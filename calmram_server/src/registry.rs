@@ -0,0 +1,62 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+use rocket::tokio::sync::mpsc;
+
+pub type ConnectionId = u64;
+
+/// The WebSocket connections currently open on one endpoint, so the server can
+/// push messages to them outside of a request/response exchange.
+pub struct Registry<M> {
+    next_id: AtomicU64,
+    connections: Mutex<HashMap<ConnectionId, mpsc::UnboundedSender<M>>>,
+}
+
+impl<M: Clone> Registry<M> {
+    pub fn new() -> Self {
+        Registry {
+            next_id: AtomicU64::new(0),
+            connections: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Adds a connection, which stays registered until the returned
+    /// [`Registration`] is dropped.
+    pub fn register(&self) -> Registration<'_, M> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = mpsc::unbounded_channel();
+        self.connections.lock().unwrap().insert(id, sender);
+        Registration {
+            registry: self,
+            id,
+            receiver,
+        }
+    }
+
+    pub fn broadcast(&self, message: &M) {
+        for sender in self.connections.lock().unwrap().values() {
+            let _ = sender.send(message.clone());
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.connections.lock().unwrap().len()
+    }
+}
+
+pub struct Registration<'a, M> {
+    registry: &'a Registry<M>,
+    pub id: ConnectionId,
+    pub receiver: mpsc::UnboundedReceiver<M>,
+}
+
+impl<M> Drop for Registration<'_, M> {
+    fn drop(&mut self) {
+        self.registry.connections.lock().unwrap().remove(&self.id);
+    }
+}
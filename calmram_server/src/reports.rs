@@ -0,0 +1,163 @@
+use std::sync::Arc;
+
+use calmram_lib::Money;
+use rocket::{
+    http::Status,
+    serde::json::Json,
+    time::{Date, PrimitiveDateTime},
+    State,
+};
+use rocket_db_pools::Connection;
+use serde::Serialize;
+use sqlx::postgres::types::PgMoney;
+
+use crate::{adjustments::Manager, config::MenuConfig, Db};
+
+/// Sales over a range of days, net of voids and refunds.
+#[derive(Serialize, Debug)]
+pub struct SalesReport {
+    pub orders: i64,
+    /// What the orders came to before discounts and tax.
+    pub gross_sales: Money,
+    pub discounts: Money,
+    pub tax: Money,
+    /// What customers tipped, which is part of `collected`.
+    pub tips: Money,
+    /// What customers paid.
+    pub collected: Money,
+    pub voids: Money,
+    pub refunds: Money,
+    /// What was collected less everything given back.
+    pub net: Money,
+}
+
+#[derive(sqlx::FromRow)]
+struct SalesRow {
+    orders: i64,
+    subtotal: Option<PgMoney>,
+    discount: Option<PgMoney>,
+    tax: Option<PgMoney>,
+    tip: Option<PgMoney>,
+    price: Option<PgMoney>,
+}
+
+fn money(amount: Option<PgMoney>) -> Money {
+    Money::from_cents(amount.map_or(0, |amount| amount.0))
+}
+
+/// Sales on the business days from `from` to `to`. Either can be left out
+/// to report from the first order or up to now. Voids and refunds count on
+/// the day they were made, by the restaurant's clock.
+#[get("/reports/sales?<from>&<to>")]
+pub async fn sales(
+    _manager: Manager,
+    from: Option<Date>,
+    to: Option<Date>,
+    menu_config: &State<Arc<MenuConfig>>,
+    mut db: Connection<Db>,
+) -> Result<Json<SalesReport>, (Status, String)> {
+    let clock = menu_config.clock();
+
+    // Orders from before business days were recorded count on the day
+    // they were placed.
+    let sales: SalesRow = sqlx::query_as(
+        "SELECT COUNT(*) AS orders, SUM(subtotal) AS subtotal, SUM(discount) AS discount,
+                SUM(tax) AS tax, SUM(tip) AS tip, SUM(price) AS price
+         FROM orders
+         CROSS JOIN LATERAL (SELECT COALESCE(
+             business_day,
+             ((time AT TIME ZONE 'UTC') + $3::INTEGER * INTERVAL '1 second')::DATE
+         ) AS day) AS business
+         WHERE ($1::DATE IS NULL OR business.day >= $1)
+           AND ($2::DATE IS NULL OR business.day <= $2)",
+    )
+    .bind(from)
+    .bind(to)
+    .bind(clock.offset().whole_seconds())
+    .fetch_one(&mut **db)
+    .await
+    .map_err(|err| (Status::InternalServerError, err.to_string()))?;
+
+    let from = from.map(|from| clock.at(from.midnight()));
+    let until = to
+        .and_then(|to| to.next_day())
+        .map(|until| clock.at(until.midnight()));
+    let adjustments: (Option<PgMoney>, Option<PgMoney>) = sqlx::query_as(
+        "SELECT SUM(amount) FILTER (WHERE kind = 'void'),
+                SUM(amount) FILTER (WHERE kind = 'refund')
+         FROM order_adjustments
+         WHERE ($1::TIMESTAMPTZ IS NULL OR time >= $1)
+           AND ($2::TIMESTAMPTZ IS NULL OR time < $2)",
+    )
+    .bind(from)
+    .bind(until)
+    .fetch_one(&mut **db)
+    .await
+    .map_err(|err| (Status::InternalServerError, err.to_string()))?;
+
+    let (voids, refunds) = (money(adjustments.0), money(adjustments.1));
+    let collected = money(sales.price);
+    Ok(Json(SalesReport {
+        orders: sales.orders,
+        gross_sales: money(sales.subtotal),
+        discounts: money(sales.discount),
+        tax: money(sales.tax),
+        tips: money(sales.tip),
+        collected,
+        voids,
+        refunds,
+        net: collected - voids - refunds,
+    }))
+}
+
+/// Tips over a shift, for sharing out at the end of it.
+#[derive(Serialize, Debug)]
+pub struct TipReport {
+    pub orders: i64,
+    /// How many of `orders` left a tip.
+    pub tipped_orders: i64,
+    pub tips: Money,
+    /// Tips given back because their order was voided.
+    pub voided: Money,
+    pub net: Money,
+}
+
+/// Tips on orders placed from `from` until just before `to`, both by the
+/// restaurant's clock and written like `2024-06-01T17:00`. Either can be
+/// left out to report from the first order or up to now.
+#[get("/reports/tips?<from>&<to>")]
+pub async fn tips(
+    _manager: Manager,
+    from: Option<PrimitiveDateTime>,
+    to: Option<PrimitiveDateTime>,
+    menu_config: &State<Arc<MenuConfig>>,
+    mut db: Connection<Db>,
+) -> Result<Json<TipReport>, (Status, String)> {
+    let clock = menu_config.clock();
+    let (orders, tipped_orders, tips, voided): (i64, i64, Option<PgMoney>, Option<PgMoney>) =
+        sqlx::query_as(
+            "SELECT COUNT(*),
+                    COUNT(*) FILTER (WHERE tip > 0::MONEY),
+                    SUM(tip),
+                    SUM(tip) FILTER (WHERE EXISTS (
+                        SELECT 1 FROM order_adjustments
+                        WHERE order_id = orders.id AND kind = 'void'))
+             FROM orders
+             WHERE ($1::TIMESTAMPTZ IS NULL OR time >= $1)
+               AND ($2::TIMESTAMPTZ IS NULL OR time < $2)",
+        )
+        .bind(from.map(|from| clock.at(from)))
+        .bind(to.map(|to| clock.at(to)))
+        .fetch_one(&mut **db)
+        .await
+        .map_err(|err| (Status::InternalServerError, err.to_string()))?;
+
+    let (tips, voided) = (money(tips), money(voided));
+    Ok(Json(TipReport {
+        orders,
+        tipped_orders,
+        tips,
+        voided,
+        net: tips - voided,
+    }))
+}